@@ -1,6 +1,6 @@
 use core::fmt;
 
-use crate::token::{Literal, Token};
+use crate::token::{Literal, Span, Token};
 
 pub mod ast_generator;
 pub mod ast_print;
@@ -9,7 +9,7 @@ pub mod ast_print;
 pub enum Expr {
     Binary(Box<Expr>, Token, Box<Expr>),
     Grouping(Box<Expr>),
-    Literal(Literal),
+    Literal(Literal, Span),
     Unary(Token, Box<Expr>),
     Variable(Token),
     Assign(Token, Box<Expr>),
@@ -17,8 +17,20 @@ pub enum Expr {
     Call(Box<Expr>, Token, Vec<Expr>),
     Get(Box<Expr>, Token),
     Set(Box<Expr>, Token, Box<Expr>),
+    // `object.name op= value`; the object is evaluated once
+    CompoundSet(Box<Expr>, Token, Token, Box<Expr>),
     Super(Token, Token),
     This(Token),
+    List(Token, Vec<Expr>, Span),
+    Map(Token, Vec<(Expr, Expr)>, Span),
+    Index(Box<Expr>, Token, Box<Expr>),
+    IndexSet(Box<Expr>, Token, Box<Expr>, Box<Expr>),
+    // a function expression; the token is `fun`, or `=>` for the arrow
+    // form, whose body is a single return
+    Lambda(Token, Vec<Token>, Vec<Stmt>, Span),
+    // an expression embedded in a string with `${...}`; evaluates to the
+    // string `print` would show for it
+    Interpolate(Box<Expr>),
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -45,19 +57,31 @@ pub enum Stmt {
         then_branch: Box<Stmt>,
         else_branch: Option<Box<Stmt>>,
     },
+    // `increment` is the third clause of a desugared `for`; it runs after
+    // every iteration, including one cut short by `continue`
     While {
         condition: Expr,
         body: Box<Stmt>,
+        increment: Option<Expr>,
     },
+    Break {
+        keyword: Token,
+    },
+    Continue {
+        keyword: Token,
+    },
+    // `doc` is the text of the `///` comments right before the declaration
     Function {
         name: Token,
         params: Vec<Token>,
         body: Vec<Stmt>,
+        doc: Option<String>,
     },
     Class {
         name: Token,
         superclass: Option<Expr>,
         methods: Vec<Stmt>,
+        doc: Option<String>,
     },
 }
 
@@ -66,7 +90,7 @@ impl fmt::Display for Expr {
         match self {
             Expr::Binary(left, op, right) => write!(f, "({} {} {})", op.lexeme, left, right),
             Expr::Grouping(expr) => write!(f, "(group {})", expr),
-            Expr::Literal(lit) => write!(f, "{}", lit),
+            Expr::Literal(lit, _) => write!(f, "{}", lit),
             Expr::Unary(op, expr) => write!(f, "({} {})", op.lexeme, expr),
             Expr::Variable(name) => write!(f, "{}", name.lexeme),
             Expr::Assign(name, expr) => write!(f, "(assign {} {})", name.lexeme, expr),
@@ -77,8 +101,35 @@ impl fmt::Display for Expr {
             }
             Expr::Get(object, _token) => write!(f, "{}", object),
             Expr::Set(object, _token, value) => write!(f, "{} = {}", object, value),
+            Expr::CompoundSet(object, _token, op, value) => {
+                write!(f, "{} {} {}", object, op.lexeme, value)
+            }
             Expr::This(name) => write!(f, "{}", name.lexeme),
             Expr::Super(name, _token) => write!(f, "{}", name.lexeme),
+            Expr::List(_bracket, elements, _) => {
+                let element_str: Vec<String> = elements.iter().map(|e| format!("{}", e)).collect();
+                write!(f, "[{}]", element_str.join(", "))
+            }
+            Expr::Map(_brace, entries, _) => {
+                let entry_str: Vec<String> = entries
+                    .iter()
+                    .map(|(key, value)| format!("{}: {}", key, value))
+                    .collect();
+                write!(f, "{{{}}}", entry_str.join(", "))
+            }
+            Expr::Index(object, _bracket, index) => write!(f, "{}[{}]", object, index),
+            Expr::IndexSet(object, _bracket, index, value) => {
+                write!(f, "{}[{}] = {}", object, index, value)
+            }
+            Expr::Lambda(_keyword, params, body, _) => {
+                let params_str: Vec<String> = params.iter().map(|p| p.lexeme.clone()).collect();
+                write!(f, "(fun ({}) ", params_str.join(", "))?;
+                for stmt in body {
+                    write!(f, "{} ", stmt)?;
+                }
+                write!(f, ")")
+            }
+            Expr::Interpolate(expr) => write!(f, "${{{}}}", expr),
         }
     }
 }
@@ -113,17 +164,36 @@ impl fmt::Display for Stmt {
                     write!(f, "(if {} {})", condition, then_branch)
                 }
             }
-            Stmt::While { condition, body } => {
+            Stmt::While {
+                condition,
+                body,
+                increment: Some(increment),
+            } => write!(f, "(while {} {} {})", condition, body, increment),
+            Stmt::While {
+                condition,
+                body,
+                increment: None,
+            } => {
                 write!(f, "(while {} {})", condition, body)
             }
-            Stmt::Function { name, params, body } => {
+            Stmt::Function {
+                name,
+                params,
+                body,
+                doc,
+            } => {
                 let params_str: Vec<String> = params.iter().map(|p| p.lexeme.clone()).collect();
                 write!(f, "(fun {}({}) ", name.lexeme, params_str.join(", "))?;
+                if let Some(doc) = doc {
+                    write!(f, "(doc {:?}) ", doc)?;
+                }
                 for stmt in body {
                     write!(f, "{} ", stmt)?;
                 }
                 write!(f, ")")
             }
+            Stmt::Break { .. } => write!(f, "(break)"),
+            Stmt::Continue { .. } => write!(f, "(continue)"),
             Stmt::Return { keyword, value } => {
                 if let Some(val) = value {
                     write!(f, "(return {} {})", keyword.lexeme, val)
@@ -135,8 +205,12 @@ impl fmt::Display for Stmt {
                 name,
                 superclass,
                 methods,
+                doc,
             } => {
                 write!(f, "(class {} ", name.lexeme)?;
+                if let Some(doc) = doc {
+                    write!(f, "(doc {:?}) ", doc)?;
+                }
                 if let Some(expr) = superclass {
                     write!(f, "superclass {} ", expr)?;
                 }
@@ -160,8 +234,27 @@ pub trait ExprVisitor<T> {
     fn visit_call_expr(&mut self, callee: &Expr, paren: &Token, arguments: &[Expr]) -> T;
     fn visit_get_expr(&mut self, object: &Expr, _name: &Token) -> T;
     fn visit_set_expr(&mut self, object: &Expr, _name: &Token, value: &Expr) -> T;
+    fn visit_compound_set_expr(
+        &mut self,
+        object: &Expr,
+        name: &Token,
+        op: &Token,
+        value: &Expr,
+    ) -> T;
     fn visit_this_expr(&mut self, _name: &Token) -> T;
     fn visit_super_expr(&mut self, _name: &Token, _method: &Token) -> T;
+    fn visit_list_expr(&mut self, bracket: &Token, elements: &[Expr]) -> T;
+    fn visit_map_expr(&mut self, brace: &Token, entries: &[(Expr, Expr)]) -> T;
+    fn visit_index_expr(&mut self, object: &Expr, bracket: &Token, index: &Expr) -> T;
+    fn visit_index_set_expr(
+        &mut self,
+        object: &Expr,
+        bracket: &Token,
+        index: &Expr,
+        value: &Expr,
+    ) -> T;
+    fn visit_lambda_expr(&mut self, keyword: &Token, params: &[Token], body: &[Stmt]) -> T;
+    fn visit_interpolate_expr(&mut self, expr: &Expr) -> T;
 }
 
 pub trait StmtVisitor<T> {
@@ -175,13 +268,76 @@ pub trait StmtVisitor<T> {
         then_branch: &Stmt,
         else_branch: &Option<Box<Stmt>>,
     ) -> T;
-    fn visit_while_stmt(&mut self, condition: &Expr, body: &Stmt) -> T;
+    fn visit_while_stmt(&mut self, condition: &Expr, body: &Stmt, increment: &Option<Expr>) -> T;
+    fn visit_break_stmt(&mut self, keyword: &Token) -> T;
+    fn visit_continue_stmt(&mut self, keyword: &Token) -> T;
     fn visit_function_stmt(&mut self, name: &Token, params: &[Token], body: &[Stmt]) -> T;
     fn visit_return_stmt(&mut self, keyword: &Token, value: &Option<Expr>) -> T;
     fn visit_class_stmt(&mut self, name: &Token, superclass: &Option<Expr>, methods: &[Stmt]) -> T;
 }
 
 impl Stmt {
+    /// Source range covered by the statement's own tokens and expressions.
+    /// Keywords and punctuation that the tree doesn't keep are left out, so
+    /// `var x = 1;` covers `x = 1`. An empty block keeps nothing and has no
+    /// span.
+    pub fn span(&self) -> Option<Span> {
+        fn cover(span: Span, other: Option<Span>) -> Span {
+            other.map_or(span, |other| span.to(other))
+        }
+        fn statements(span: Span, statements: &[Stmt]) -> Span {
+            statements
+                .iter()
+                .fold(span, |span, stmt| cover(span, stmt.span()))
+        }
+
+        Some(match self {
+            Stmt::Expression { expression } | Stmt::Print { expression } => expression.span(),
+            Stmt::Var { name, initializer } => {
+                cover(name.span, initializer.as_ref().map(Expr::span))
+            }
+            Stmt::Return { keyword, value } => cover(keyword.span, value.as_ref().map(Expr::span)),
+            Stmt::Break { keyword } | Stmt::Continue { keyword } => keyword.span,
+            Stmt::Block { statements: block } => {
+                let first = block.iter().find_map(Stmt::span)?;
+                statements(first, block)
+            }
+            Stmt::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                let span = cover(condition.span(), then_branch.span());
+                cover(span, else_branch.as_ref().and_then(|branch| branch.span()))
+            }
+            Stmt::While {
+                condition,
+                body,
+                increment,
+            } => {
+                let span = cover(condition.span(), body.span());
+                cover(span, increment.as_ref().map(Expr::span))
+            }
+            Stmt::Function {
+                name, params, body, ..
+            } => {
+                let span = params
+                    .iter()
+                    .fold(name.span, |span, param| span.to(param.span));
+                statements(span, body)
+            }
+            Stmt::Class {
+                name,
+                superclass,
+                methods,
+                ..
+            } => statements(
+                cover(name.span, superclass.as_ref().map(Expr::span)),
+                methods,
+            ),
+        })
+    }
+
     pub fn accept<T>(&self, visitor: &mut impl StmtVisitor<T>) -> T {
         match self {
             Stmt::Expression { expression } => visitor.visit_expression_stmt(expression),
@@ -193,26 +349,56 @@ impl Stmt {
                 then_branch,
                 else_branch,
             } => visitor.visit_if_stmt(condition, then_branch, else_branch),
-            Stmt::While { condition, body } => visitor.visit_while_stmt(condition, body),
-            Stmt::Function { name, params, body } => {
-                visitor.visit_function_stmt(name, params, body)
-            }
+            Stmt::While {
+                condition,
+                body,
+                increment,
+            } => visitor.visit_while_stmt(condition, body, increment),
+            Stmt::Break { keyword } => visitor.visit_break_stmt(keyword),
+            Stmt::Continue { keyword } => visitor.visit_continue_stmt(keyword),
+            Stmt::Function {
+                name, params, body, ..
+            } => visitor.visit_function_stmt(name, params, body),
             Stmt::Return { keyword, value } => visitor.visit_return_stmt(keyword, value),
             Stmt::Class {
                 name,
                 superclass,
                 methods,
+                ..
             } => visitor.visit_class_stmt(name, superclass, methods),
         }
     }
 }
 
 impl Expr {
+    /// Source range covered by the expression, from its first token to its last.
+    pub fn span(&self) -> Span {
+        match self {
+            Expr::Binary(left, _, right) | Expr::Logical(left, _, right) => {
+                left.span().to(right.span())
+            }
+            Expr::Grouping(expr) | Expr::Interpolate(expr) => expr.span(),
+            Expr::Literal(_, span) => *span,
+            Expr::Unary(op, expr) => op.span.to(expr.span()),
+            Expr::Variable(name) | Expr::This(name) => name.span,
+            Expr::Assign(name, value) => name.span.to(value.span()),
+            Expr::Call(callee, paren, _) => callee.span().to(paren.span),
+            Expr::Get(object, name) => object.span().to(name.span),
+            Expr::Set(object, _, value) | Expr::CompoundSet(object, _, _, value) => {
+                object.span().to(value.span())
+            }
+            Expr::Super(keyword, method) => keyword.span.to(method.span),
+            Expr::List(_, _, span) | Expr::Map(_, _, span) | Expr::Lambda(_, _, _, span) => *span,
+            Expr::Index(object, bracket, _) => object.span().to(bracket.span),
+            Expr::IndexSet(object, _, _, value) => object.span().to(value.span()),
+        }
+    }
+
     pub fn accept<T>(&self, visitor: &mut impl ExprVisitor<T>) -> T {
         match self {
             Expr::Binary(left, op, right) => visitor.visit_binary_expr(left, op, right),
             Expr::Grouping(expr) => visitor.visit_grouping_expr(expr),
-            Expr::Literal(lit) => visitor.visit_literal_expr(lit),
+            Expr::Literal(lit, _) => visitor.visit_literal_expr(lit),
             Expr::Unary(op, expr) => visitor.visit_unary_expr(op, expr),
             Expr::Variable(name) => visitor.visit_variable_expr(name),
             Expr::Assign(name, value) => visitor.visit_assign_expr(name, value),
@@ -222,8 +408,21 @@ impl Expr {
             }
             Expr::Get(object, name) => visitor.visit_get_expr(object, name),
             Expr::Set(object, name, value) => visitor.visit_set_expr(object, name, value),
+            Expr::CompoundSet(object, name, op, value) => {
+                visitor.visit_compound_set_expr(object, name, op, value)
+            }
             Expr::This(name) => visitor.visit_this_expr(name),
             Expr::Super(keyword, method) => visitor.visit_super_expr(keyword, method),
+            Expr::List(bracket, elements, _) => visitor.visit_list_expr(bracket, elements),
+            Expr::Map(brace, entries, _) => visitor.visit_map_expr(brace, entries),
+            Expr::Index(object, bracket, index) => visitor.visit_index_expr(object, bracket, index),
+            Expr::IndexSet(object, bracket, index, value) => {
+                visitor.visit_index_set_expr(object, bracket, index, value)
+            }
+            Expr::Lambda(keyword, params, body, _) => {
+                visitor.visit_lambda_expr(keyword, params, body)
+            }
+            Expr::Interpolate(expr) => visitor.visit_interpolate_expr(expr),
         }
     }
 }
@@ -51,25 +51,30 @@ impl Environment {
     }
 
     pub fn get_at(&self, distance: usize, name: &str) -> Option<Object> {
-        Environment::ancestor(Rc::new(RefCell::new(self.clone())), distance)
-            .and_then(|env| env.borrow().hashmap.get(name).cloned())
+        if distance == 0 {
+            return self.hashmap.get(name).cloned();
+        }
+        self.enclosing
+            .as_ref()
+            .and_then(|enclosing| enclosing.borrow().get_at(distance - 1, name))
     }
 
-    pub fn ancestor(
-        env: Rc<RefCell<Environment>>,
-        mut distance: usize,
-    ) -> Option<Rc<RefCell<Environment>>> {
-        let mut environment = Rc::clone(&env);
-
-        while distance > 0 {
-            let parent_opt = environment.borrow().enclosing.clone();
-            match parent_opt {
-                Some(parent) => environment = parent,
-                None => return None,
-            }
-            distance -= 1;
+    pub fn assign_at(
+        &mut self,
+        distance: usize,
+        token: &Token,
+        value: Object,
+    ) -> Result<(), NZErrors> {
+        if distance == 0 {
+            self.hashmap.insert(token.lexeme.clone(), value);
+            return Ok(());
+        }
+        match &self.enclosing {
+            Some(enclosing) => enclosing.borrow_mut().assign_at(distance - 1, token, value),
+            None => Err(NZErrors::RuntimeError(
+                token.clone(),
+                format!("Undefined variable '{}'.", token.lexeme),
+            )),
         }
-
-        Some(environment)
     }
 }
@@ -1,36 +1,330 @@
-use std::process::exit;
+use core::fmt;
 
 use crate::{
     object::Object,
-    token::{token_types::TokenType, Token},
+    token::{token_types::TokenType, Span, Token},
 };
 
+#[derive(Debug)]
 pub enum NZErrors {
     ParseError(Token, String),
+    ResolveError(Token, String),
+    CompileError(Token, String),
     RuntimeError(Token, String),
     FileReadError(String),
     Return(Object),
+    // unwind to the innermost loop, like `Return` does to the function
+    Break,
+    Continue,
 }
 
-fn print_error(errtype: &str, token: &Token, message: &str) {
-    if token.token_type == TokenType::EOF {
-        println!("{} Error at end: {}", errtype, message);
-    } else {
-        println!("{} at '{}': {}", errtype, token.line, message);
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ErrorKind {
+    Parse,
+    Resolve,
+    Compile,
+    Runtime,
+    Io,
+    // `return`, `break` or `continue` unwound past the code that should
+    // have caught it; only reachable by running statements the resolver
+    // hasn't checked
+    Internal,
+}
+
+/// An error rendered against the source it came from. Produced by
+/// `NZErrors::diagnostic`; deciding what to do with it (print, exit, show in
+/// an editor) is up to the caller.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub kind: ErrorKind,
+    pub message: String,
+    pub line: Option<u32>,
+    pub column: Option<usize>,
+    // width in characters of the offending token at `column`
+    pub width: usize,
+    // columns `start..end` of the whole offending construct on `line`
+    pub range: Option<(usize, usize)>,
+    pub snippet: Option<String>,
+}
+
+impl NZErrors {
+    /// Runtime error raised by a native function. It has no location of its
+    /// own; the interpreter points it at the call site.
+    pub fn native(message: impl Into<String>) -> NZErrors {
+        NZErrors::RuntimeError(Token::default(), message.into())
+    }
+
+    /// Gives errors that were raised without a source location (see
+    /// `native`, or a compile error about a literal) the location of `token`.
+    pub fn or_at(self, token: &Token) -> NZErrors {
+        match self {
+            NZErrors::RuntimeError(at, message) if at.line == 0 => {
+                NZErrors::RuntimeError(token.clone(), message)
+            }
+            NZErrors::CompileError(at, message) if at.line == 0 => {
+                NZErrors::CompileError(token.clone(), message)
+            }
+            other => other,
+        }
+    }
+
+    /// The stage that reported the error.
+    ///
+    /// ```
+    /// use interpreter::{parser::Parser, scanner::Scanner, ErrorKind, Interpreter};
+    ///
+    /// let tokens = Scanner::new("return 1;".to_string()).scan_tokens().unwrap();
+    /// let statements = Parser::new(tokens).parse().unwrap();
+    /// // without the resolver nothing stops `return` escaping to the top level
+    /// let error = Interpreter::new().interpret(&statements).unwrap_err();
+    /// assert_eq!(error.kind(), ErrorKind::Internal);
+    /// assert_eq!(error.message(), "Can't return from top-level code.");
+    /// ```
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            NZErrors::ParseError(..) => ErrorKind::Parse,
+            NZErrors::ResolveError(..) => ErrorKind::Resolve,
+            NZErrors::CompileError(..) => ErrorKind::Compile,
+            NZErrors::RuntimeError(..) => ErrorKind::Runtime,
+            NZErrors::FileReadError(_) => ErrorKind::Io,
+            NZErrors::Return(_) | NZErrors::Break | NZErrors::Continue => ErrorKind::Internal,
+        }
+    }
+
+    pub fn token(&self) -> Option<&Token> {
+        match self {
+            NZErrors::ParseError(token, _)
+            | NZErrors::ResolveError(token, _)
+            | NZErrors::CompileError(token, _)
+            | NZErrors::RuntimeError(token, _) => Some(token),
+            NZErrors::FileReadError(_)
+            | NZErrors::Return(_)
+            | NZErrors::Break
+            | NZErrors::Continue => None,
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            NZErrors::ParseError(_, message)
+            | NZErrors::ResolveError(_, message)
+            | NZErrors::CompileError(_, message)
+            | NZErrors::RuntimeError(_, message)
+            | NZErrors::FileReadError(message) => message,
+            NZErrors::Return(_) => "Can't return from top-level code.",
+            NZErrors::Break => "Can't use 'break' outside of a loop.",
+            NZErrors::Continue => "Can't use 'continue' outside of a loop.",
+        }
     }
-    if errtype == "Runtime Error" {
-        exit(69);
+
+    /// Widens the span of the error's token to also cover `span`, so the
+    /// report underlines the whole expression and not only its operator.
+    pub fn with_span(mut self, span: Span) -> NZErrors {
+        if let NZErrors::ParseError(token, _)
+        | NZErrors::ResolveError(token, _)
+        | NZErrors::CompileError(token, _)
+        | NZErrors::RuntimeError(token, _) = &mut self
+        {
+            token.span = token.span.to(span);
+        }
+        self
+    }
+
+    pub fn diagnostic(&self, source: &str) -> Diagnostic {
+        let mut diagnostic = Diagnostic {
+            kind: self.kind(),
+            message: self.message().to_string(),
+            line: None,
+            column: None,
+            width: 1,
+            range: None,
+            snippet: None,
+        };
+        let Some(token) = self.token().filter(|token| token.line > 0) else {
+            return diagnostic;
+        };
+        diagnostic.line = Some(token.line);
+        diagnostic.column = Some(token.column as usize);
+        // only the first line of a multi-line lexeme can be shown
+        let lexeme = token.lexeme.split('\n').next().unwrap_or_default();
+        diagnostic.width = lexeme.chars().count().max(1);
+
+        // find the token's line in `source`; the token may come from an
+        // earlier REPL entry, so only trust it if the lexeme is really there
+        let Some((line_start, text)) = source
+            .split('\n')
+            .scan(0, |offset, text| {
+                let start = *offset;
+                *offset += text.len() + 1;
+                Some((start, text))
+            })
+            .nth(token.line as usize - 1)
+        else {
+            return diagnostic;
+        };
+        let at_column: String = text.chars().skip(token.column as usize - 1).collect();
+        if !at_column.starts_with(lexeme) {
+            return diagnostic;
+        }
+        diagnostic.snippet = Some(text.trim_end_matches('\r').to_string());
+
+        let line_end = line_start + text.len();
+        let start = token.span.start.clamp(line_start, line_end);
+        let end = token.span.end.clamp(start, line_end);
+        if let (Some(before), Some(covered)) =
+            (source.get(line_start..start), source.get(start..end))
+        {
+            let start_column = before.chars().count() + 1;
+            diagnostic.range = Some((start_column, start_column + covered.chars().count()));
+        }
+        diagnostic
     }
-    exit(1);
 }
 
-impl NZErrors {
-    pub fn report_error(&self) {
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            NZErrors::ParseError(token, message) => print_error("Parse Error", token, message),
-            NZErrors::RuntimeError(token, message) => print_error("Runtime Error", token, message),
-            NZErrors::FileReadError(message) => println!("{}", message),
-            NZErrors::Return(_) => panic!("Return should never be reported as an error!"),
+            ErrorKind::Parse => write!(f, "Parse Error"),
+            ErrorKind::Resolve => write!(f, "Resolve Error"),
+            ErrorKind::Compile => write!(f, "Compile Error"),
+            ErrorKind::Runtime => write!(f, "Runtime Error"),
+            ErrorKind::Io => write!(f, "IO Error"),
+            ErrorKind::Internal => write!(f, "Internal Error"),
+        }
+    }
+}
+
+impl fmt::Display for NZErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.token() {
+            Some(token) if token.token_type == TokenType::EOF => {
+                write!(f, "{} at end: {}", self.kind(), self.message())
+            }
+            Some(token) => write!(
+                f,
+                "{} at line {}, column {} ('{}'): {}",
+                self.kind(),
+                token.line,
+                token.column,
+                token.lexeme,
+                self.message()
+            ),
+            None => write!(f, "{}", self.message()),
+        }
+    }
+}
+
+impl std::error::Error for NZErrors {}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.line, self.column) {
+            (Some(line), Some(column)) => write!(
+                f,
+                "{} at line {}, column {}: {}",
+                self.kind, line, column, self.message
+            )?,
+            (Some(line), None) => write!(f, "{} at line {}: {}", self.kind, line, self.message)?,
+            _ => write!(f, "{}: {}", self.kind, self.message)?,
         }
+        if let (Some(line), Some(snippet)) = (self.line, &self.snippet) {
+            write!(f, "\n{:>5} | {}", line, snippet)?;
+            if let Some(column) = self.column {
+                // '^' under the offending token, '~' under the rest of the expression
+                let (start, end) = self.range.unwrap_or((column, column + self.width));
+                let underline: String = (start.min(column)..end.max(column + self.width))
+                    .map(|c| {
+                        if c >= column && c < column + self.width {
+                            '^'
+                        } else {
+                            '~'
+                        }
+                    })
+                    .collect();
+                // tabs are kept so the underline lines up however wide they render
+                let indent: String = snippet
+                    .chars()
+                    .chain(std::iter::repeat(' '))
+                    .take(start.min(column) - 1)
+                    .map(|c| if c == '\t' { '\t' } else { ' ' })
+                    .collect();
+                write!(f, "\n{:>5} | {}{}", "", indent, underline)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for Diagnostic {}
+
+#[cfg(test)]
+mod tests {
+    use crate::Interpreter;
+
+    /// The report for the first error `source` produces, line by line.
+    fn render(source: &str) -> Vec<String> {
+        let errors = Interpreter::new().run(source).unwrap_err();
+        let report = errors[0].diagnostic(source).to_string();
+        report.lines().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn caret_under_single_column_token() {
+        assert_eq!(
+            render("print x;"),
+            [
+                "Runtime Error at line 1, column 7: Undefined variable 'x'.",
+                "    1 | print x;",
+                "      |       ^",
+            ]
+        );
+    }
+
+    #[test]
+    fn tildes_under_rest_of_expression() {
+        assert_eq!(
+            render("var a = 1;\nprint a  +  nil;"),
+            [
+                "Runtime Error at line 2, column 10: Cannot add two different types",
+                "    2 | print a  +  nil;",
+                "      |       ~~~^~~~~~",
+            ]
+        );
+    }
+
+    #[test]
+    fn underline_keeps_tabs_of_the_line() {
+        assert_eq!(
+            render("\t\tprint 1 + nil;"),
+            [
+                "Runtime Error at line 1, column 11: Cannot add two different types",
+                "    1 | \t\tprint 1 + nil;",
+                "      | \t\t      ~~^~~~~",
+            ]
+        );
+    }
+
+    #[test]
+    fn columns_count_characters_not_bytes() {
+        assert_eq!(
+            render("var s = \"日本\"; print \"ü\" + s + nil;"),
+            [
+                "Runtime Error at line 1, column 29: Cannot add two different types",
+                "    1 | var s = \"日本\"; print \"ü\" + s + nil;",
+                "      |                     ~~~~~~~~^~~~~",
+            ]
+        );
+    }
+
+    #[test]
+    fn error_at_end_of_input_points_past_the_line() {
+        assert_eq!(
+            render("print 1 +"),
+            [
+                "Parse Error at line 1, column 10: Expect expression, got ",
+                "    1 | print 1 +",
+                "      |          ^",
+            ]
+        );
     }
 }
@@ -1,8 +1,40 @@
 use super::Interpreter;
 use crate::{error::NZErrors, object::Object};
 use core::fmt;
+use std::any::Any;
 
-pub trait LoxCallable: fmt::Display + fmt::Debug {
-    fn arity(&self) -> usize;
-    fn call(&self, interpreter: &mut Interpreter, args: &Vec<Object>) -> Result<Object, NZErrors>;
+/// How many arguments a callable accepts.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Arity {
+    Fixed(usize),
+    AtLeast(usize),
+    // inclusive bounds, for trailing optional arguments
+    Range(usize, usize),
+}
+
+impl Arity {
+    pub fn accepts(&self, count: usize) -> bool {
+        match self {
+            Arity::Fixed(n) => count == *n,
+            Arity::AtLeast(n) => count >= *n,
+            Arity::Range(min, max) => count >= *min && count <= *max,
+        }
+    }
+}
+
+impl fmt::Display for Arity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Arity::Fixed(n) => write!(f, "{}", n),
+            Arity::AtLeast(n) => write!(f, "at least {}", n),
+            Arity::Range(min, max) => write!(f, "{} to {}", min, max),
+        }
+    }
+}
+
+/// Anything scripts can call. `Any` lets a backend recognise its own
+/// function types behind an `Rc<dyn LoxCallable>` and call them natively.
+pub trait LoxCallable: Any + fmt::Display + fmt::Debug {
+    fn arity(&self) -> Arity;
+    fn call(&self, interpreter: &mut Interpreter, args: &[Object]) -> Result<Object, NZErrors>;
 }
@@ -4,21 +4,29 @@ use std::{cell::RefCell, collections::HashMap, rc::Rc};
 use crate::{object::Object, token::Token};
 
 use super::{
-    loxcallable::LoxCallable, loxfunction::LoxFunction, loxinstance::LoxInstance, Interpreter,
+    loxcallable::{Arity, LoxCallable},
+    loxinstance::LoxInstance,
+    Interpreter,
 };
 
+/// A function that can be stored in a class and bound to an instance of it.
+/// Each backend provides its own.
+pub trait LoxMethod: LoxCallable {
+    fn bind(&self, instance: Rc<RefCell<LoxInstance>>) -> Rc<dyn LoxCallable>;
+}
+
 #[derive(Clone, Debug)]
 pub struct LoxClass {
     pub name: Token,
     pub superclass: Option<Rc<LoxClass>>,
-    pub methods: HashMap<String, Rc<RefCell<LoxFunction>>>,
+    pub methods: HashMap<String, Rc<dyn LoxMethod>>,
 }
 
 impl LoxClass {
     pub fn new(
         name: Token,
         superclass: Option<Rc<LoxClass>>,
-        methods: HashMap<String, Rc<RefCell<LoxFunction>>>,
+        methods: HashMap<String, Rc<dyn LoxMethod>>,
     ) -> Self {
         Self {
             name,
@@ -27,7 +35,7 @@ impl LoxClass {
         }
     }
 
-    pub fn find_method(&self, name: &str) -> Option<Rc<RefCell<LoxFunction>>> {
+    pub fn find_method(&self, name: &str) -> Option<Rc<dyn LoxMethod>> {
         if let Some(method) = self.methods.get(name) {
             Some(Rc::clone(method))
         } else if let Some(superclass) = &self.superclass {
@@ -36,36 +44,41 @@ impl LoxClass {
             None
         }
     }
+
+    /// Whether this class is `other` or inherits from it, directly or not.
+    pub fn is_subclass_of(&self, other: &LoxClass) -> bool {
+        std::ptr::eq(self, other)
+            || self
+                .superclass
+                .as_ref()
+                .is_some_and(|superclass| superclass.is_subclass_of(other))
+    }
 }
 
-impl LoxCallable for LoxClass {
-    fn arity(&self) -> usize {
+impl LoxClass {
+    pub fn arity(&self) -> Arity {
         if let Some(initializer) = self.find_method("init") {
-            initializer.borrow().arity()
+            initializer.arity()
         } else {
-            0
+            Arity::Fixed(0)
         }
     }
 
-    fn call(
-        &self,
+    /// Creates a new instance of `klass` and runs its initializer on it.
+    /// Takes the `Rc` so every instance points at the same class object.
+    pub fn call(
+        klass: &Rc<LoxClass>,
         interpreter: &mut Interpreter,
-        args: &Vec<Object>,
+        args: &[Object],
     ) -> Result<Object, super::NZErrors> {
-        let loxinstance = LoxInstance::new(&Rc::new(LoxClass::new(
-            self.name.clone(),
-            self.superclass.clone(),
-            self.methods.clone(),
-        )));
-        let initializer = self.find_method("init");
+        let instance = Rc::new(RefCell::new(LoxInstance::new(klass)));
 
-        if let Some(initializer) = initializer {
+        if let Some(initializer) = klass.find_method("init") {
             initializer
-                .borrow()
-                .bind(Rc::new(RefCell::new(loxinstance.clone())))
+                .bind(Rc::clone(&instance))
                 .call(interpreter, args)?;
         }
-        Ok(Object::Instance(Rc::new(RefCell::new(loxinstance))))
+        Ok(Object::Instance(instance))
     }
 }
 
@@ -3,7 +3,12 @@ use std::{cell::RefCell, rc::Rc};
 
 use crate::{ast::Stmt, environment::Environment, error::NZErrors, object::Object, token::Token};
 
-use super::{loxcallable::LoxCallable, loxinstance::LoxInstance, Interpreter};
+use super::{
+    loxcallable::{Arity, LoxCallable},
+    loxclass::LoxMethod,
+    loxinstance::LoxInstance,
+    Interpreter, MAX_CALL_DEPTH,
+};
 
 #[derive(Clone, Debug)]
 pub struct LoxFunction {
@@ -31,60 +36,65 @@ impl LoxFunction {
         }
     }
 
-    pub fn bind(&self, instance: Rc<RefCell<LoxInstance>>) -> LoxFunction {
+    /// Runs the body in a fresh environment holding the arguments.
+    fn run(&self, interpreter: &mut Interpreter, args: &[Object]) -> Result<Object, NZErrors> {
         let environment = Environment::new(Some(Rc::clone(&self.closure)));
-        environment.borrow_mut().define(
-            "this".to_string(),
-            Object::Instance(Rc::new(RefCell::new(instance.borrow().clone()))),
-        );
-        LoxFunction {
+        for (param, arg) in self.params.iter().zip(args.iter()) {
+            environment
+                .borrow_mut()
+                .define(param.lexeme.clone(), arg.clone());
+        }
+
+        match interpreter.execute_block(&self.body, environment) {
+            // an initializer always hands back the instance it was bound to,
+            // even after a bare `return;`
+            Ok(_) | Err(NZErrors::Return(_)) if self.initializer => {
+                match self.closure.borrow().get_at(0, "this") {
+                    Some(x) => Ok(x),
+                    None => Ok(Object::Nill),
+                }
+            }
+            Ok(_) => Ok(Object::Nill),
+            Err(NZErrors::Return(value)) => Ok(value),
+            Err(err) => Err(err),
+        }
+    }
+}
+
+impl LoxMethod for LoxFunction {
+    fn bind(&self, instance: Rc<RefCell<LoxInstance>>) -> Rc<dyn LoxCallable> {
+        let environment = Environment::new(Some(Rc::clone(&self.closure)));
+        environment
+            .borrow_mut()
+            .define("this".to_string(), Object::Instance(instance));
+        Rc::new(LoxFunction {
             name: self.name.clone(),
             params: self.params.clone(),
             body: self.body.clone(),
             closure: environment,
             initializer: self.initializer,
-        }
+        })
     }
 }
 
 impl fmt::Display for LoxFunction {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let function = Stmt::Function {
-            name: self.name.clone(),
-            params: self.params.clone(),
-            body: self.body.clone(),
-        };
-
-        write!(f, "{}", function)
+        write!(f, "<fn {}>", self.name.lexeme)
     }
 }
 
 impl LoxCallable for LoxFunction {
-    fn arity(&self) -> usize {
-        self.params.len()
+    fn arity(&self) -> Arity {
+        Arity::Fixed(self.params.len())
     }
 
-    fn call(&self, interpreter: &mut Interpreter, args: &Vec<Object>) -> Result<Object, NZErrors> {
-        let environment = Environment::new(Some(Rc::clone(&self.closure)));
-        for (param, arg) in self.params.iter().zip(args.iter()) {
-            environment
-                .borrow_mut()
-                .define(param.lexeme.clone(), arg.clone());
-        }
-
-        match interpreter.execute_block(&self.body, environment) {
-            Ok(_) => {
-                if self.initializer {
-                    match self.closure.borrow().get_at(0, "this") {
-                        Some(x) => Ok(x),
-                        None => Ok(Object::Nill),
-                    }
-                } else {
-                    Ok(Object::Nill)
-                }
-            }
-            Err(NZErrors::Return(value)) => Ok(value),
-            Err(err) => Err(err),
+    fn call(&self, interpreter: &mut Interpreter, args: &[Object]) -> Result<Object, NZErrors> {
+        if interpreter.depth == MAX_CALL_DEPTH {
+            return Err(NZErrors::native("Stack overflow."));
         }
+        interpreter.depth += 1;
+        let result = self.run(interpreter, args);
+        interpreter.depth -= 1;
+        result
     }
 }
@@ -19,22 +19,27 @@ impl LoxInstance {
         }
     }
 
-    pub fn get(&self, name: &Token) -> Result<Object, NZErrors> {
-        if let Some(value) = self.fields.get(&name.lexeme) {
-            Ok(value.clone())
+    /// Takes the shared handle rather than `&self` so that bound methods see
+    /// this very instance as `this`, not a copy of it.
+    pub fn get(instance: &Rc<RefCell<LoxInstance>>, name: &Token) -> Result<Object, NZErrors> {
+        if let Some(value) = instance.borrow().fields.get(&name.lexeme) {
+            return Ok(value.clone());
+        }
+        let method = instance.borrow().klass.find_method(&name.lexeme);
+        if let Some(method) = method {
+            Ok(Object::Callable(method.bind(Rc::clone(instance))))
         } else {
-            if let Some(method) = self.klass.find_method(&name.lexeme) {
-                let bound = method.borrow().bind(Rc::new(RefCell::new(self.clone())));
-                Ok(Object::Callable(Rc::new(bound)))
-            } else {
-                Err(NZErrors::RuntimeError(
-                    name.clone(),
-                    format!("Undefined property '{}'.", name.lexeme),
-                ))
-            }
+            Err(NZErrors::RuntimeError(
+                name.clone(),
+                format!("Undefined property '{}'.", name.lexeme),
+            ))
         }
     }
 
+    pub fn class(&self) -> &Rc<LoxClass> {
+        &self.klass
+    }
+
     pub fn set(&mut self, name: &Token, value: Object) {
         self.fields.insert(name.lexeme.clone(), value);
     }
@@ -1,39 +1,298 @@
-mod clockfn;
 pub mod loxcallable;
 pub mod loxclass;
 mod loxfunction;
 pub mod loxinstance;
-use std::{cell::RefCell, collections::HashMap, rc::Rc};
+pub mod nativefunction;
+mod natives;
+pub(crate) mod operators;
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, HashMap},
+    rc::Rc,
+};
 
-use clockfn::ClockFn;
-use loxcallable::LoxCallable;
-use loxclass::LoxClass;
+use loxcallable::{Arity, LoxCallable};
+use loxclass::{LoxClass, LoxMethod};
 use loxfunction::LoxFunction;
+use loxinstance::LoxInstance;
+use nativefunction::{BoundNative, NativeFunction};
 
 use crate::{
     ast::{Expr, ExprVisitor, Stmt, StmtVisitor},
     environment::Environment,
     error::NZErrors,
-    object::Object,
+    object::{MapKey, Object},
+    parser::Parser,
+    resolver::Resolver,
+    scanner::Scanner,
     token::{token_types::TokenType, Literal, Token},
+    vm::Stack,
 };
 
+/// How many calls may be active at once, in either backend. Deep enough for
+/// any sane recursion, shallow enough to fail cleanly with "Stack overflow."
+/// instead of overflowing the native stack.
+pub const MAX_CALL_DEPTH: usize = 4096;
+
 pub struct Interpreter {
-    globals: Rc<RefCell<Environment>>,
+    pub(crate) globals: Rc<RefCell<Environment>>,
     environment: Rc<RefCell<Environment>>,
+    // token id -> number of environments between use and binding
+    locals: HashMap<usize, usize>,
+    // value stack of the bytecode VM, which runs against these globals too
+    pub(crate) stack: Stack,
+    // builtins that strings, lists and maps also offer as methods
+    methods: HashMap<String, Rc<NativeFunction>>,
+    // calls currently running on either backend; the VM counts its frames
+    // in here while it calls out, so recursion through both still stops at
+    // `MAX_CALL_DEPTH`
+    pub(crate) depth: usize,
 }
 
 impl Interpreter {
     pub fn new() -> Self {
         let globals = Environment::new(None);
 
-        globals
-            .borrow_mut()
-            .define("clock".to_string(), Object::Callable(Rc::new(ClockFn)));
-
-        Self {
+        let mut interpreter = Self {
             environment: Rc::clone(&globals),
             globals: Rc::clone(&globals),
+            locals: HashMap::new(),
+            stack: Stack::default(),
+            methods: HashMap::new(),
+            depth: 0,
+        };
+
+        interpreter.define_native("clock", Arity::Fixed(0), |_, _| {
+            use std::time::{SystemTime, UNIX_EPOCH};
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_err(|e| NZErrors::native(e.to_string()))?
+                .as_millis();
+            Ok(Object::Number((now as f64) / 1000.0))
+        });
+        natives::list::define(&mut interpreter);
+        natives::map::define(&mut interpreter);
+        natives::math::define(&mut interpreter);
+        natives::string::define(&mut interpreter);
+        natives::types::define(&mut interpreter);
+        natives::io::define(&mut interpreter);
+
+        interpreter
+    }
+
+    /// Scans, parses, resolves and runs `source` as a whole program. Globals
+    /// it defines stay visible to later calls on the same interpreter.
+    ///
+    /// ```
+    /// use interpreter::{Interpreter, Object};
+    ///
+    /// let mut interpreter = Interpreter::new();
+    /// interpreter.run("fun square(n) { return n * n; }").unwrap();
+    /// interpreter.run("var area = square(4);").unwrap();
+    /// assert!(matches!(interpreter.get_global("area"), Some(Object::Number(n)) if n == 16.0));
+    ///
+    /// let errors = interpreter.run("print missing;").unwrap_err();
+    /// assert_eq!(errors[0].message(), "Undefined variable 'missing'.");
+    /// ```
+    pub fn run(&mut self, source: &str) -> Result<(), Vec<NZErrors>> {
+        let tokens = Scanner::new(source.to_string())
+            .scan_tokens()
+            .map_err(|e| vec![e])?;
+        let statements = Parser::new(tokens).parse()?;
+        Resolver::new(self)
+            .resolve(&statements)
+            .map_err(|e| vec![e])?;
+        self.interpret(&statements).map_err(|e| vec![e])
+    }
+
+    /// Evaluates `source` as a single expression, e.g. `"limit * 2"`, and
+    /// returns its value.
+    ///
+    /// ```
+    /// use interpreter::{Interpreter, Object};
+    ///
+    /// let mut interpreter = Interpreter::new();
+    /// interpreter.run("var limit = 10;").unwrap();
+    /// let value = interpreter.eval("limit * 2").unwrap();
+    /// assert!(matches!(value, Object::Number(n) if n == 20.0));
+    /// ```
+    pub fn eval(&mut self, source: &str) -> Result<Object, Vec<NZErrors>> {
+        let tokens = Scanner::new(source.to_string())
+            .scan_tokens()
+            .map_err(|e| vec![e])?;
+        let expression = Parser::new(tokens)
+            .parse_expression()
+            .map_err(|e| vec![e])?;
+        let statement = Stmt::Expression { expression };
+        Resolver::new(self)
+            .resolve(std::slice::from_ref(&statement))
+            .map_err(|e| vec![e])?;
+        match &statement {
+            Stmt::Expression { expression } => self.evaluate(expression).map_err(|e| vec![e]),
+            _ => unreachable!(),
+        }
+    }
+
+    /// The value of the global `name`, or `None` if no script or embedder
+    /// has defined it.
+    ///
+    /// ```
+    /// use interpreter::{Interpreter, Object};
+    ///
+    /// let mut interpreter = Interpreter::new();
+    /// interpreter.run("var greeting = \"hi\";").unwrap();
+    /// assert!(matches!(interpreter.get_global("greeting"), Some(Object::String(s)) if s == "hi"));
+    /// assert!(interpreter.get_global("nope").is_none());
+    /// ```
+    pub fn get_global(&self, name: &str) -> Option<Object> {
+        self.globals.borrow().get_at(0, name)
+    }
+
+    /// Defines `name` in the global scope, replacing any previous value.
+    ///
+    /// ```
+    /// use interpreter::{Interpreter, Object};
+    ///
+    /// let mut interpreter = Interpreter::new();
+    /// interpreter.set_global("base", Object::Number(40.0));
+    /// interpreter.run("var answer = base + 2;").unwrap();
+    /// assert!(matches!(interpreter.get_global("answer"), Some(Object::Number(n)) if n == 42.0));
+    /// ```
+    pub fn set_global(&mut self, name: &str, value: Object) {
+        self.globals.borrow_mut().define(name.to_string(), value);
+    }
+
+    /// Makes a host function implemented in Rust callable from scripts.
+    ///
+    /// ```
+    /// use std::fmt;
+    ///
+    /// use interpreter::interpreter::loxcallable::{Arity, LoxCallable};
+    /// use interpreter::{Interpreter, NZErrors, Object};
+    ///
+    /// #[derive(Debug)]
+    /// struct Twice;
+    ///
+    /// impl fmt::Display for Twice {
+    ///     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    ///         write!(f, "<native fn twice>")
+    ///     }
+    /// }
+    ///
+    /// impl LoxCallable for Twice {
+    ///     fn arity(&self) -> Arity {
+    ///         Arity::Fixed(1)
+    ///     }
+    ///
+    ///     fn call(&self, _: &mut Interpreter, args: &[Object]) -> Result<Object, NZErrors> {
+    ///         match &args[0] {
+    ///             Object::Number(n) => Ok(Object::Number(n * 2.0)),
+    ///             _ => Err(NZErrors::native("twice() expects a number.")),
+    ///         }
+    ///     }
+    /// }
+    ///
+    /// let mut interpreter = Interpreter::new();
+    /// interpreter.define_callable("twice", Twice);
+    /// interpreter.run("var x = twice(21);").unwrap();
+    /// assert!(matches!(interpreter.get_global("x"), Some(Object::Number(n)) if n == 42.0));
+    /// ```
+    pub fn define_callable(&mut self, name: &str, callable: impl LoxCallable + 'static) {
+        self.set_global(name, Object::Callable(Rc::new(callable)));
+    }
+
+    /// Registers a builtin backed by a Rust closure. Use `Arity::AtLeast`
+    /// for variadic functions.
+    ///
+    /// ```
+    /// use std::{cell::RefCell, rc::Rc};
+    ///
+    /// use interpreter::interpreter::loxcallable::Arity;
+    /// use interpreter::{Interpreter, NZErrors, Object};
+    ///
+    /// let mut interpreter = Interpreter::new();
+    /// // the closure can keep state of its own on the Rust side
+    /// let log = Rc::new(RefCell::new(Vec::new()));
+    /// let sink = Rc::clone(&log);
+    /// interpreter.define_native("record", Arity::AtLeast(1), move |_, args| {
+    ///     sink.borrow_mut().extend(args.iter().map(Object::to_string));
+    ///     Ok(Object::Number(args.len() as f64))
+    /// });
+    /// interpreter.define_native("fail", Arity::Fixed(0), |_, _| {
+    ///     Err(NZErrors::native("fail() always fails."))
+    /// });
+    ///
+    /// interpreter.run("var count = record(\"a\", 1 + 1);").unwrap();
+    /// assert_eq!(*log.borrow(), ["a", "2"]);
+    /// assert!(matches!(interpreter.get_global("count"), Some(Object::Number(n)) if n == 2.0));
+    ///
+    /// let errors = interpreter.run("fail();").unwrap_err();
+    /// assert_eq!(errors[0].message(), "fail() always fails.");
+    /// assert_eq!(errors[0].token().map(|token| token.line), Some(1));
+    /// ```
+    pub fn define_native(
+        &mut self,
+        name: &str,
+        arity: Arity,
+        function: impl Fn(&mut Interpreter, &[Object]) -> Result<Object, NZErrors> + 'static,
+    ) {
+        self.define_callable(name, NativeFunction::new(name, arity, function));
+    }
+
+    /// Like `define_native`, and also makes the builtin callable as a method
+    /// of strings, lists and maps: `value.name(args)` is `name(value, args)`.
+    pub fn define_native_method(
+        &mut self,
+        name: &str,
+        arity: Arity,
+        function: impl Fn(&mut Interpreter, &[Object]) -> Result<Object, NZErrors> + 'static,
+    ) {
+        let native = Rc::new(NativeFunction::new(name, arity, function));
+        self.set_global(name, Object::Callable(native.clone()));
+        self.methods.insert(name.to_string(), native);
+    }
+
+    /// `object.name` for every kind of value, shared with the VM.
+    pub(crate) fn get_property(&self, object: Object, name: &Token) -> Result<Object, NZErrors> {
+        match &object {
+            Object::Instance(instance) => LoxInstance::get(instance, name),
+            Object::String(_) | Object::List(_) | Object::Map(_) => {
+                match self.methods.get(&name.lexeme) {
+                    Some(function) => Ok(Object::Callable(Rc::new(BoundNative::new(
+                        object,
+                        Rc::clone(function),
+                    )))),
+                    None => Err(NZErrors::RuntimeError(
+                        name.clone(),
+                        format!("Undefined property '{}'.", name.lexeme),
+                    )),
+                }
+            }
+            _ => Err(NZErrors::RuntimeError(
+                name.clone(),
+                "Only instances have properties.".to_string(),
+            )),
+        }
+    }
+
+    /// Called by the resolver for every local variable reference.
+    pub fn resolve(&mut self, name: &Token, depth: usize) {
+        self.locals.insert(name.id, depth);
+    }
+
+    fn look_up_variable(&self, name: &Token) -> Result<Object, NZErrors> {
+        match self.locals.get(&name.id) {
+            Some(distance) => self
+                .environment
+                .borrow()
+                .get_at(*distance, &name.lexeme)
+                .ok_or_else(|| {
+                    NZErrors::RuntimeError(
+                        name.clone(),
+                        format!("Undefined variable '{}'.", name.lexeme),
+                    )
+                }),
+            None => self.globals.borrow().get(name),
         }
     }
     pub fn interpret(&mut self, stmplist: &[Stmt]) -> Result<(), NZErrors> {
@@ -65,128 +324,14 @@ impl Interpreter {
         result
     }
 
-    fn evaluate(&mut self, expr: &Expr) -> Result<Object, NZErrors> {
+    pub fn evaluate(&mut self, expr: &Expr) -> Result<Object, NZErrors> {
         expr.accept(self)
     }
+}
 
-    fn istrusthy(&self, obj: &Object) -> bool {
-        match obj {
-            Object::Boolean(b) => *b,
-            Object::Nill => false,
-            _ => true,
-        }
-    }
-
-    fn issub(&self, right: &Object, left: &Object, op: &Token) -> Result<Object, NZErrors> {
-        match (left, right) {
-            (Object::Number(l), Object::Number(r)) => Ok(Object::Number(l - r)),
-            _ => Err(NZErrors::RuntimeError(
-                op.clone(),
-                "Cannot subtract two different types".to_string(),
-            )),
-        }
-    }
-
-    fn ismul(&self, right: &Object, left: &Object, op: &Token) -> Result<Object, NZErrors> {
-        match (left, right) {
-            (Object::Number(l), Object::Number(r)) => Ok(Object::Number(l * r)),
-            _ => Err(NZErrors::RuntimeError(
-                op.clone(),
-                "Cannot multiply two different types".to_string(),
-            )),
-        }
-    }
-
-    fn isdiv(&self, right: &Object, left: &Object, op: &Token) -> Result<Object, NZErrors> {
-        match (left, right) {
-            (Object::Number(l), Object::Number(r)) => Ok(Object::Number(l / r)),
-            _ => Err(NZErrors::RuntimeError(
-                op.clone(),
-                "Cannot divide two different types".to_string(),
-            )),
-        }
-    }
-
-    fn isadd(&self, right: &Object, left: &Object, op: &Token) -> Result<Object, NZErrors> {
-        match (left, right) {
-            (Object::Number(l), Object::Number(r)) => Ok(Object::Number(l + r)),
-            (Object::String(l), Object::String(r)) => Ok(Object::String(format!("{}{}", l, r))),
-            (Object::Number(l), Object::String(r)) => Ok(Object::String(format!("{}{}", l, r))),
-            (Object::String(l), Object::Number(r)) => Ok(Object::String(format!("{}{}", l, r))),
-            _ => Err(NZErrors::RuntimeError(
-                op.clone(),
-                "Cannot add two different types".to_string(),
-            )),
-        }
-    }
-
-    fn isgreaterequal(
-        &self,
-        right: &Object,
-        left: &Object,
-        op: &Token,
-    ) -> Result<Object, NZErrors> {
-        match (left, right) {
-            (Object::Number(l), Object::Number(r)) => Ok(Object::Boolean(l >= r)),
-            _ => Err(NZErrors::RuntimeError(
-                op.clone(),
-                "Cannot compare two different types".to_string(),
-            )),
-        }
-    }
-
-    fn islessequal(&self, right: &Object, left: &Object, op: &Token) -> Result<Object, NZErrors> {
-        match (left, right) {
-            (Object::Number(l), Object::Number(r)) => Ok(Object::Boolean(l <= r)),
-            _ => Err(NZErrors::RuntimeError(
-                op.clone(),
-                "Cannot compare two different types".to_string(),
-            )),
-        }
-    }
-
-    fn isless(&self, right: &Object, left: &Object, op: &Token) -> Result<Object, NZErrors> {
-        match (left, right) {
-            (Object::Number(l), Object::Number(r)) => Ok(Object::Boolean(l < r)),
-            _ => Err(NZErrors::RuntimeError(
-                op.clone(),
-                "Cannot compare two different types".to_string(),
-            )),
-        }
-    }
-
-    fn isgreater(&self, right: &Object, left: &Object, op: &Token) -> Result<Object, NZErrors> {
-        match (left, right) {
-            (Object::Number(l), Object::Number(r)) => Ok(Object::Boolean(l > r)),
-            _ => Err(NZErrors::RuntimeError(
-                op.clone(),
-                "Cannot compare two different types".to_string(),
-            )),
-        }
-    }
-
-    fn isequal(&self, right: &Object, left: &Object, op: &Token) -> Result<Object, NZErrors> {
-        match (left, right) {
-            (Object::Number(l), Object::Number(r)) => Ok(Object::Boolean(l == r)),
-            (Object::String(l), Object::String(r)) => Ok(Object::Boolean(l == r)),
-            (Object::Nill, Object::Nill) => Ok(Object::Boolean(false)),
-            _ => Err(NZErrors::RuntimeError(
-                op.clone(),
-                "Cannot compare two different types".to_string(),
-            )),
-        }
-    }
-
-    fn isnotequal(&self, right: &Object, left: &Object, op: &Token) -> Result<Object, NZErrors> {
-        match (left, right) {
-            (Object::Number(l), Object::Number(r)) => Ok(Object::Boolean(l != r)),
-            (Object::String(l), Object::String(r)) => Ok(Object::Boolean(l != r)),
-            (Object::Nill, Object::Nill) => Ok(Object::Boolean(false)),
-            _ => Err(NZErrors::RuntimeError(
-                op.clone(),
-                "Cannot compare two different types".to_string(),
-            )),
-        }
+impl Default for Interpreter {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
@@ -197,29 +342,16 @@ impl ExprVisitor<Result<Object, NZErrors>> for Interpreter {
         op: &Token,
         right: &Expr,
     ) -> Result<Object, NZErrors> {
-        let left = self.evaluate(left)?;
-        let right = self.evaluate(right)?;
-
-        match op.token_type {
-            TokenType::PLUS => self.isadd(&right, &left, op),
-            TokenType::MINUS => self.issub(&right, &left, op),
-            TokenType::STAR => self.ismul(&right, &left, op),
-            TokenType::SLASH => self.isdiv(&right, &left, op),
-            TokenType::EQUALEQUAL => self.isequal(&right, &left, op),
-            TokenType::BANGEQUAL => self.isnotequal(&right, &left, op),
-            TokenType::GREATER => self.isgreater(&right, &left, op),
-            TokenType::GREATEREQUAL => self.isgreaterequal(&right, &left, op),
-            TokenType::LESS => self.isless(&right, &left, op),
-            TokenType::LESSEQUAL => self.islessequal(&right, &left, op),
-            _ => Err(NZErrors::RuntimeError(
-                op.clone(),
-                "Unsupported binary operator".to_string(),
-            )),
-        }
+        let left_value = self.evaluate(left)?;
+        let right_value = self.evaluate(right)?;
+
+        // the span walks both operands, so it is only worked out for an error
+        operators::binary(&left_value, op, &right_value)
+            .map_err(|e| e.with_span(left.span().to(right.span())))
     }
 
     fn visit_grouping_expr(&mut self, expr: &Expr) -> Result<Object, NZErrors> {
-        return self.evaluate(expr);
+        self.evaluate(expr)
     }
 
     fn visit_literal_expr(&mut self, literal: &Literal) -> Result<Object, NZErrors> {
@@ -233,33 +365,23 @@ impl ExprVisitor<Result<Object, NZErrors>> for Interpreter {
 
     fn visit_unary_expr(&mut self, op: &Token, expr: &Expr) -> Result<Object, NZErrors> {
         let right = self.evaluate(expr)?;
-        match op.token_type {
-            TokenType::MINUS => match right {
-                Object::Number(n) => Ok(Object::Number(-n)),
-                _ => {
-                    return Err(NZErrors::RuntimeError(
-                        op.clone(),
-                        "Operand must be a number for unary minus.".to_string(),
-                    ))
-                }
-            },
-            TokenType::BANG => Ok(Object::Boolean(self.istrusthy(&right))),
-            _ => {
-                return Err(NZErrors::RuntimeError(
-                    op.clone(),
-                    "Unknown operator.".to_string(),
-                ))
-            }
-        }
+        operators::unary(op, &right).map_err(|e| e.with_span(op.span.to(expr.span())))
     }
 
     fn visit_variable_expr(&mut self, name: &Token) -> Result<Object, NZErrors> {
-        self.environment.borrow().get(name)
+        self.look_up_variable(name)
     }
 
     fn visit_assign_expr(&mut self, name: &Token, value: &Expr) -> Result<Object, NZErrors> {
         let value = self.evaluate(value)?;
-        self.environment.borrow_mut().assign(name, value.clone())?;
+        match self.locals.get(&name.id) {
+            Some(distance) => {
+                self.environment
+                    .borrow_mut()
+                    .assign_at(*distance, name, value.clone())?
+            }
+            None => self.globals.borrow_mut().assign(name, value.clone())?,
+        }
         Ok(value)
     }
 
@@ -271,11 +393,11 @@ impl ExprVisitor<Result<Object, NZErrors>> for Interpreter {
     ) -> Result<Object, NZErrors> {
         let left = self.evaluate(left)?;
         if op.token_type == TokenType::OR {
-            if self.istrusthy(&left) {
+            if operators::is_truthy(&left) {
                 return Ok(left);
             }
         } else {
-            if !self.istrusthy(&left) {
+            if !operators::is_truthy(&left) {
                 return Ok(left);
             }
         }
@@ -297,7 +419,7 @@ impl ExprVisitor<Result<Object, NZErrors>> for Interpreter {
 
         match call {
             Object::Callable(function) => {
-                if args.len() != function.arity() {
+                if !function.arity().accepts(args.len()) {
                     return Err(NZErrors::RuntimeError(
                         paren.clone(),
                         format!(
@@ -307,12 +429,12 @@ impl ExprVisitor<Result<Object, NZErrors>> for Interpreter {
                         ),
                     ));
                 }
-                function.call(self, &args)
+                function.call(self, &args).map_err(|e| e.or_at(paren))
             }
 
             Object::Class(class) => {
                 // Same arity check for initializer
-                if args.len() != class.arity() {
+                if !class.arity().accepts(args.len()) {
                     return Err(NZErrors::RuntimeError(
                         paren.clone(),
                         format!(
@@ -322,7 +444,7 @@ impl ExprVisitor<Result<Object, NZErrors>> for Interpreter {
                         ),
                     ));
                 }
-                class.call(self, &args)
+                LoxClass::call(&class, self, &args).map_err(|e| e.or_at(paren))
             }
 
             _ => Err(NZErrors::RuntimeError(
@@ -333,26 +455,41 @@ impl ExprVisitor<Result<Object, NZErrors>> for Interpreter {
     }
 
     fn visit_get_expr(&mut self, object: &Expr, name: &Token) -> Result<Object, NZErrors> {
+        let object = self.evaluate(object)?;
+        self.get_property(object, name)
+    }
+
+    fn visit_set_expr(
+        &mut self,
+        object: &Expr,
+        name: &Token,
+        value: &Expr,
+    ) -> Result<Object, NZErrors> {
         let object = self.evaluate(object)?;
         if let Object::Instance(instance) = object {
-            instance.borrow().get(name)
+            let value = self.evaluate(value)?;
+            instance.borrow_mut().set(name, value.clone());
+            Ok(value)
         } else {
             Err(NZErrors::RuntimeError(
                 name.clone(),
-                "Only instances have properties.".to_string(),
+                "Only instances have fields.".to_string(),
             ))
         }
     }
 
-    fn visit_set_expr(
+    fn visit_compound_set_expr(
         &mut self,
         object: &Expr,
         name: &Token,
+        op: &Token,
         value: &Expr,
     ) -> Result<Object, NZErrors> {
-        let object = self.evaluate(object)?;
-        if let Object::Instance(instance) = object {
-            let value = self.evaluate(value)?;
+        if let Object::Instance(instance) = self.evaluate(object)? {
+            let current = LoxInstance::get(&instance, name)?;
+            let operand = self.evaluate(value)?;
+            let value = operators::binary(&current, op, &operand)
+                .map_err(|e| e.with_span(object.span().to(value.span())))?;
             instance.borrow_mut().set(name, value.clone());
             Ok(value)
         } else {
@@ -364,12 +501,20 @@ impl ExprVisitor<Result<Object, NZErrors>> for Interpreter {
     }
 
     fn visit_this_expr(&mut self, name: &Token) -> Result<Object, NZErrors> {
-        self.environment.borrow().get(name)
+        self.look_up_variable(name)
     }
 
     fn visit_super_expr(&mut self, name: &Token, method: &Token) -> Result<Object, NZErrors> {
+        let distance = *self.locals.get(&name.id).ok_or_else(|| {
+            NZErrors::RuntimeError(name.clone(), "Unresolved 'super' expression.".to_string())
+        })?;
+
         // Look up "super" from the environment
-        let superclass = self.environment.borrow().get(name)?;
+        let superclass = self
+            .environment
+            .borrow()
+            .get_at(distance, "super")
+            .unwrap_or(Object::Nill);
         let superclass = match superclass {
             Object::Class(ref klass) => klass.clone(),
             _ => {
@@ -381,7 +526,7 @@ impl ExprVisitor<Result<Object, NZErrors>> for Interpreter {
         };
 
         // "this" is always one environment closer than "super"
-        let instance = self.environment.borrow().get_at(1, "this");
+        let instance = self.environment.borrow().get_at(distance - 1, "this");
         let instance = match instance {
             Some(Object::Instance(ref inst)) => inst.clone(),
             _ => {
@@ -395,8 +540,7 @@ impl ExprVisitor<Result<Object, NZErrors>> for Interpreter {
         // Find method in superclass
         if let Some(method_func) = superclass.find_method(&method.lexeme) {
             // Bind method to current instance
-            let bound = method_func.borrow().bind(instance);
-            return Ok(Object::Callable(Rc::new(bound)));
+            return Ok(Object::Callable(method_func.bind(instance)));
         }
 
         Err(NZErrors::RuntimeError(
@@ -404,6 +548,81 @@ impl ExprVisitor<Result<Object, NZErrors>> for Interpreter {
             format!("Undefined property '{}'.", method.lexeme),
         ))
     }
+
+    fn visit_list_expr(&mut self, _bracket: &Token, elements: &[Expr]) -> Result<Object, NZErrors> {
+        let mut values = Vec::with_capacity(elements.len());
+        for element in elements {
+            values.push(self.evaluate(element)?);
+        }
+        Ok(Object::List(Rc::new(RefCell::new(values))))
+    }
+
+    fn visit_map_expr(
+        &mut self,
+        brace: &Token,
+        entries: &[(Expr, Expr)],
+    ) -> Result<Object, NZErrors> {
+        let mut map = BTreeMap::new();
+        for (key_expr, value) in entries {
+            let key = MapKey::from_object(&self.evaluate(key_expr)?).map_err(|message| {
+                NZErrors::RuntimeError(brace.clone(), message).with_span(key_expr.span())
+            })?;
+            map.insert(key, self.evaluate(value)?);
+        }
+        Ok(Object::Map(Rc::new(RefCell::new(map))))
+    }
+
+    fn visit_index_expr(
+        &mut self,
+        object: &Expr,
+        bracket: &Token,
+        index: &Expr,
+    ) -> Result<Object, NZErrors> {
+        let target = self.evaluate(object)?;
+        let index = self.evaluate(index)?;
+        operators::get_index(&target, &index, bracket)
+            .map_err(|e| e.with_span(object.span().to(bracket.span)))
+    }
+
+    fn visit_index_set_expr(
+        &mut self,
+        object: &Expr,
+        bracket: &Token,
+        index: &Expr,
+        value: &Expr,
+    ) -> Result<Object, NZErrors> {
+        let target = self.evaluate(object)?;
+        let index = self.evaluate(index)?;
+        let value = self.evaluate(value)?;
+        operators::set_index(&target, &index, value.clone(), bracket)
+            .map_err(|e| e.with_span(object.span().to(bracket.span)))?;
+        Ok(value)
+    }
+
+    fn visit_lambda_expr(
+        &mut self,
+        keyword: &Token,
+        params: &[Token],
+        body: &[Stmt],
+    ) -> Result<Object, NZErrors> {
+        let name = Token {
+            lexeme: "lambda".to_string(),
+            ..keyword.clone()
+        };
+        let function = LoxFunction::new(
+            name,
+            params.to_vec(),
+            body.to_vec(),
+            self.environment.clone(),
+            false,
+        );
+        Ok(Object::Callable(Rc::new(function)))
+    }
+
+    fn visit_interpolate_expr(&mut self, expr: &Expr) -> Result<Object, NZErrors> {
+        let value = self.evaluate(expr)?;
+        Ok(Object::String(value.to_string()))
+    }
 }
 
 impl StmtVisitor<Result<(), NZErrors>> for Interpreter {
@@ -414,7 +633,7 @@ impl StmtVisitor<Result<(), NZErrors>> for Interpreter {
 
     fn visit_print_stmt(&mut self, expr: &Expr) -> Result<(), NZErrors> {
         let obj = self.evaluate(expr)?;
-        println!("{}", obj.to_string());
+        println!("{}", obj);
         Ok(())
     }
 
@@ -443,7 +662,7 @@ impl StmtVisitor<Result<(), NZErrors>> for Interpreter {
         else_branch: &Option<Box<Stmt>>,
     ) -> Result<(), NZErrors> {
         let eval = self.evaluate(condition)?;
-        if self.istrusthy(&eval) {
+        if operators::is_truthy(&eval) {
             self.execute(then_branch)?;
         } else if let Some(else_branch) = else_branch {
             self.execute(else_branch)?;
@@ -451,15 +670,35 @@ impl StmtVisitor<Result<(), NZErrors>> for Interpreter {
         Ok(())
     }
 
-    fn visit_while_stmt(&mut self, condition: &Expr, body: &Stmt) -> Result<(), NZErrors> {
+    fn visit_while_stmt(
+        &mut self,
+        condition: &Expr,
+        body: &Stmt,
+        increment: &Option<Expr>,
+    ) -> Result<(), NZErrors> {
         let mut eval = self.evaluate(condition)?;
-        while self.istrusthy(&eval) {
-            self.execute(body)?;
+        while operators::is_truthy(&eval) {
+            match self.execute(body) {
+                Ok(()) | Err(NZErrors::Continue) => {}
+                Err(NZErrors::Break) => break,
+                Err(err) => return Err(err),
+            }
+            if let Some(increment) = increment {
+                self.evaluate(increment)?;
+            }
             eval = self.evaluate(condition)?;
         }
         Ok(())
     }
 
+    fn visit_break_stmt(&mut self, _keyword: &Token) -> Result<(), NZErrors> {
+        Err(NZErrors::Break)
+    }
+
+    fn visit_continue_stmt(&mut self, _keyword: &Token) -> Result<(), NZErrors> {
+        Err(NZErrors::Continue)
+    }
+
     fn visit_function_stmt(
         &mut self,
         name: &Token,
@@ -530,15 +769,18 @@ impl StmtVisitor<Result<(), NZErrors>> for Interpreter {
         // Step 4: collect methods
         let mut meth = HashMap::new();
         for method in methods {
-            if let Stmt::Function { name, params, body } = method {
-                let function = LoxFunction::new(
+            if let Stmt::Function {
+                name, params, body, ..
+            } = method
+            {
+                let function: Rc<dyn LoxMethod> = Rc::new(LoxFunction::new(
                     name.clone(),
                     params.to_vec(),
                     body.to_vec(),
                     self.environment.clone(), // closure sees "super"
                     name.lexeme == "init",
-                );
-                meth.insert(name.lexeme.clone(), Rc::new(RefCell::new(function)));
+                ));
+                meth.insert(name.lexeme.clone(), function);
             }
         }
 
@@ -0,0 +1,96 @@
+use core::fmt;
+use std::rc::Rc;
+
+use crate::{error::NZErrors, object::Object};
+
+use super::{
+    loxcallable::{Arity, LoxCallable},
+    Interpreter,
+};
+
+pub type NativeFn = dyn Fn(&mut Interpreter, &[Object]) -> Result<Object, NZErrors>;
+
+/// A builtin implemented as a Rust closure. The interpreter checks the arity
+/// before calling, so the closure can index `args` freely.
+#[derive(Clone)]
+pub struct NativeFunction {
+    name: String,
+    arity: Arity,
+    function: Rc<NativeFn>,
+}
+
+impl NativeFunction {
+    pub fn new(
+        name: &str,
+        arity: Arity,
+        function: impl Fn(&mut Interpreter, &[Object]) -> Result<Object, NZErrors> + 'static,
+    ) -> Self {
+        Self {
+            name: name.to_string(),
+            arity,
+            function: Rc::new(function),
+        }
+    }
+}
+
+impl LoxCallable for NativeFunction {
+    fn arity(&self) -> Arity {
+        self.arity
+    }
+
+    fn call(&self, interpreter: &mut Interpreter, args: &[Object]) -> Result<Object, NZErrors> {
+        (self.function)(interpreter, args)
+    }
+}
+
+impl fmt::Debug for NativeFunction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NativeFunction")
+            .field("name", &self.name)
+            .field("arity", &self.arity)
+            .finish()
+    }
+}
+
+/// A builtin looked up as a method, as in `text.upper()`. The value it was
+/// looked up on is passed as the first argument.
+#[derive(Debug)]
+pub struct BoundNative {
+    receiver: Object,
+    function: Rc<NativeFunction>,
+}
+
+impl BoundNative {
+    pub fn new(receiver: Object, function: Rc<NativeFunction>) -> Self {
+        Self { receiver, function }
+    }
+}
+
+impl LoxCallable for BoundNative {
+    fn arity(&self) -> Arity {
+        match self.function.arity {
+            Arity::Fixed(n) => Arity::Fixed(n.saturating_sub(1)),
+            Arity::AtLeast(n) => Arity::AtLeast(n.saturating_sub(1)),
+            Arity::Range(min, max) => Arity::Range(min.saturating_sub(1), max.saturating_sub(1)),
+        }
+    }
+
+    fn call(&self, interpreter: &mut Interpreter, args: &[Object]) -> Result<Object, NZErrors> {
+        let mut all = Vec::with_capacity(args.len() + 1);
+        all.push(self.receiver.clone());
+        all.extend_from_slice(args);
+        self.function.call(interpreter, &all)
+    }
+}
+
+impl fmt::Display for BoundNative {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.function)
+    }
+}
+
+impl fmt::Display for NativeFunction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<native fn {}>", self.name)
+    }
+}
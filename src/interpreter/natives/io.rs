@@ -0,0 +1,71 @@
+use std::{
+    fs,
+    io::{self, BufRead, Write},
+    path::Path,
+};
+
+use crate::{error::NZErrors, interpreter::Interpreter, object::Object};
+
+use super::{super::loxcallable::Arity, expect_string};
+
+fn io_error(function: &str, path: &str, error: io::Error) -> NZErrors {
+    NZErrors::native(format!("{}() failed for '{}': {}.", function, path, error))
+}
+
+fn write(function: &str, args: &[Object], append: bool) -> Result<Object, NZErrors> {
+    let path = expect_string(function, &args[0])?;
+    fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .append(append)
+        .truncate(!append)
+        .open(&path)
+        .and_then(|mut file| write!(file, "{}", args[1]))
+        .map_err(|e| io_error(function, &path, e))?;
+    Ok(Object::Nill)
+}
+
+pub fn define(interpreter: &mut Interpreter) {
+    // the next line of standard input without its line ending, or nil once
+    // the input is exhausted
+    interpreter.define_native("readLine", Arity::Fixed(0), |_, _| {
+        let mut line = String::new();
+        let read = io::stdin()
+            .lock()
+            .read_line(&mut line)
+            .map_err(|e| NZErrors::native(format!("readLine() failed: {}.", e)))?;
+        if read == 0 {
+            return Ok(Object::Nill);
+        }
+        let line = line.strip_suffix('\n').unwrap_or(&line);
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        Ok(Object::String(line.to_string()))
+    });
+
+    interpreter.define_native("readFile", Arity::Fixed(1), |_, args| {
+        let path = expect_string("readFile", &args[0])?;
+        fs::read_to_string(&path)
+            .map(Object::String)
+            .map_err(|e| io_error("readFile", &path, e))
+    });
+
+    // both write the value the way `print` shows it, without a newline
+    interpreter.define_native("writeFile", Arity::Fixed(2), |_, args| {
+        write("writeFile", args, false)
+    });
+
+    interpreter.define_native("appendFile", Arity::Fixed(2), |_, args| {
+        write("appendFile", args, true)
+    });
+
+    interpreter.define_native("fileExists", Arity::Fixed(1), |_, args| {
+        let path = expect_string("fileExists", &args[0])?;
+        Ok(Object::Boolean(Path::new(&path).is_file()))
+    });
+
+    // `print` to standard error
+    interpreter.define_native("eprint", Arity::Fixed(1), |_, args| {
+        eprintln!("{}", args[0]);
+        Ok(Object::Nill)
+    });
+}
@@ -0,0 +1,58 @@
+use std::{cell::RefCell, rc::Rc};
+
+use crate::{error::NZErrors, interpreter::Interpreter, object::Object};
+
+use super::{super::loxcallable::Arity, expect_list, index};
+
+pub fn define(interpreter: &mut Interpreter) {
+    interpreter.define_native_method("len", Arity::Fixed(1), |_, args| match &args[0] {
+        Object::List(list) => Ok(Object::Number(list.borrow().len() as f64)),
+        Object::Map(map) => Ok(Object::Number(map.borrow().len() as f64)),
+        Object::String(s) => Ok(Object::Number(s.chars().count() as f64)),
+        other => Err(NZErrors::native(format!(
+            "len() expects a list, map or string, got {}.",
+            other
+        ))),
+    });
+
+    interpreter.define_native_method("push", Arity::Fixed(2), |_, args| {
+        expect_list("push", &args[0])?
+            .borrow_mut()
+            .push(args[1].clone());
+        Ok(Object::Nill)
+    });
+
+    interpreter.define_native_method("pop", Arity::Fixed(1), |_, args| {
+        expect_list("pop", &args[0])?
+            .borrow_mut()
+            .pop()
+            .ok_or_else(|| NZErrors::native("pop() from an empty list."))
+    });
+
+    interpreter.define_native_method("insert", Arity::Fixed(3), |_, args| {
+        let list = expect_list("insert", &args[0])?;
+        let at = index("List", &args[1], list.borrow().len(), true).map_err(NZErrors::native)?;
+        list.borrow_mut().insert(at, args[2].clone());
+        Ok(Object::Nill)
+    });
+
+    // slice(list, start, end?) copies list[start..end] into a new list
+    interpreter.define_native_method("slice", Arity::Range(2, 3), |_, args| {
+        let list = expect_list("slice", &args[0])?;
+        let list = list.borrow();
+        let start = index("List", &args[1], list.len(), true).map_err(NZErrors::native)?;
+        let end = match args.get(2) {
+            Some(end) => index("List", end, list.len(), true).map_err(NZErrors::native)?,
+            None => list.len(),
+        };
+        if start > end {
+            return Err(NZErrors::native(format!(
+                "slice() start {} is after end {}.",
+                start, end
+            )));
+        }
+        Ok(Object::List(Rc::new(RefCell::new(
+            list[start..end].to_vec(),
+        ))))
+    });
+}
@@ -0,0 +1,40 @@
+use std::{cell::RefCell, rc::Rc};
+
+use crate::{
+    error::NZErrors,
+    interpreter::Interpreter,
+    object::{MapKey, Object},
+};
+
+use super::{super::loxcallable::Arity, expect_map};
+
+fn key(value: &Object) -> Result<MapKey, NZErrors> {
+    MapKey::from_object(value).map_err(NZErrors::native)
+}
+
+pub fn define(interpreter: &mut Interpreter) {
+    interpreter.define_native_method("has", Arity::Fixed(2), |_, args| {
+        let map = expect_map("has", &args[0])?;
+        let found = map.borrow().contains_key(&key(&args[1])?);
+        Ok(Object::Boolean(found))
+    });
+
+    // returns the removed value, or nil if the key was not there
+    interpreter.define_native_method("remove", Arity::Fixed(2), |_, args| {
+        let map = expect_map("remove", &args[0])?;
+        let removed = map.borrow_mut().remove(&key(&args[1])?);
+        Ok(removed.unwrap_or(Object::Nill))
+    });
+
+    interpreter.define_native_method("keys", Arity::Fixed(1), |_, args| {
+        let map = expect_map("keys", &args[0])?;
+        let keys = map.borrow().keys().map(MapKey::to_object).collect();
+        Ok(Object::List(Rc::new(RefCell::new(keys))))
+    });
+
+    interpreter.define_native_method("values", Arity::Fixed(1), |_, args| {
+        let map = expect_map("values", &args[0])?;
+        let values = map.borrow().values().cloned().collect();
+        Ok(Object::List(Rc::new(RefCell::new(values))))
+    });
+}
@@ -0,0 +1,133 @@
+use std::{
+    cell::Cell,
+    f64::consts::{E, PI},
+    rc::Rc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{error::NZErrors, interpreter::Interpreter, object::Object};
+
+use super::{super::loxcallable::Arity, expect_number};
+
+/// A splitmix64 generator: small, fast, and the same sequence for the same
+/// seed on every platform, which scripts rely on for reproducible runs.
+struct Random {
+    state: Cell<u64>,
+}
+
+impl Random {
+    fn next(&self) -> u64 {
+        let state = self.state.get().wrapping_add(0x9E37_79B9_7F4A_7C15);
+        self.state.set(state);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// A number in `[0, 1)` with the full 53 bits of precision.
+    fn unit(&self) -> f64 {
+        (self.next() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+fn expect_integer(function: &str, value: &Object) -> Result<f64, NZErrors> {
+    match value {
+        Object::Number(n) if n.fract() == 0.0 => Ok(*n),
+        _ => Err(NZErrors::native(format!(
+            "{}() expects an integer, got {}.",
+            function, value
+        ))),
+    }
+}
+
+pub fn define(interpreter: &mut Interpreter) {
+    interpreter.set_global("PI", Object::Number(PI));
+    interpreter.set_global("E", Object::Number(E));
+
+    let unary = [
+        ("sqrt", f64::sqrt as fn(f64) -> f64),
+        ("abs", f64::abs),
+        ("floor", f64::floor),
+        ("ceil", f64::ceil),
+        // halfway cases round away from zero
+        ("round", f64::round),
+        ("sin", f64::sin),
+        ("cos", f64::cos),
+        ("tan", f64::tan),
+        ("exp", f64::exp),
+    ];
+    for (name, function) in unary {
+        interpreter.define_native(name, Arity::Fixed(1), move |_, args| {
+            Ok(Object::Number(function(expect_number(name, &args[0])?)))
+        });
+    }
+
+    interpreter.define_native("pow", Arity::Fixed(2), |_, args| {
+        let base = expect_number("pow", &args[0])?;
+        let exponent = expect_number("pow", &args[1])?;
+        Ok(Object::Number(base.powf(exponent)))
+    });
+
+    // log(x) is the natural logarithm, log(x, base) the one to `base`
+    interpreter.define_native("log", Arity::Range(1, 2), |_, args| {
+        let x = expect_number("log", &args[0])?;
+        Ok(Object::Number(match args.get(1) {
+            Some(base) => x.log(expect_number("log", base)?),
+            None => x.ln(),
+        }))
+    });
+
+    interpreter.define_native("min", Arity::AtLeast(1), |_, args| {
+        args.iter()
+            .try_fold(f64::INFINITY, |min, arg| {
+                Ok(min.min(expect_number("min", arg)?))
+            })
+            .map(Object::Number)
+    });
+
+    interpreter.define_native("max", Arity::AtLeast(1), |_, args| {
+        args.iter()
+            .try_fold(f64::NEG_INFINITY, |max, arg| {
+                Ok(max.max(expect_number("max", arg)?))
+            })
+            .map(Object::Number)
+    });
+
+    let seed = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |now| now.as_nanos() as u64);
+    let random = Rc::new(Random {
+        state: Cell::new(seed),
+    });
+
+    let generator = Rc::clone(&random);
+    interpreter.define_native("seedRandom", Arity::Fixed(1), move |_, args| {
+        let seed = expect_integer("seedRandom", &args[0])?;
+        generator.state.set(seed as i64 as u64);
+        Ok(Object::Nill)
+    });
+
+    let generator = Rc::clone(&random);
+    interpreter.define_native("random", Arity::Fixed(0), move |_, _| {
+        Ok(Object::Number(generator.unit()))
+    });
+
+    // randomInt(min, max) includes both ends
+    interpreter.define_native("randomInt", Arity::Fixed(2), move |_, args| {
+        let min = expect_integer("randomInt", &args[0])?;
+        let max = expect_integer("randomInt", &args[1])?;
+        if min > max {
+            return Err(NZErrors::native(format!(
+                "randomInt() min {} is greater than max {}.",
+                min, max
+            )));
+        }
+        // beyond 2^53 not every integer in between is a number
+        if max - min >= (1u64 << 53) as f64 {
+            return Err(NZErrors::native("randomInt() range is too large."));
+        }
+        let range = (max - min) as u64 + 1;
+        Ok(Object::Number(min + (random.next() % range) as f64))
+    });
+}
@@ -0,0 +1,82 @@
+//! Builtin functions registered in the global scope of every interpreter.
+
+pub mod io;
+pub mod list;
+pub mod map;
+pub mod math;
+pub mod string;
+pub mod types;
+
+use std::rc::Rc;
+
+use crate::{
+    error::NZErrors,
+    object::{ListRef, MapRef, Object},
+};
+
+/// Converts `value` to a position in a list or string of length `len`;
+/// `noun` ("List" or "String") names it in the error. With `allow_end`, `len`
+/// itself is accepted, for inserting or slicing at the end.
+pub(crate) fn index(
+    noun: &str,
+    value: &Object,
+    len: usize,
+    allow_end: bool,
+) -> Result<usize, String> {
+    match value {
+        Object::Number(n) if n.fract() == 0.0 => {
+            let limit = if allow_end { len + 1 } else { len };
+            if *n >= 0.0 && (*n as usize) < limit {
+                Ok(*n as usize)
+            } else {
+                Err(format!(
+                    "Index {} out of range for {} of length {}.",
+                    n,
+                    noun.to_lowercase(),
+                    len
+                ))
+            }
+        }
+        _ => Err(format!("{} index must be an integer, got {}.", noun, value)),
+    }
+}
+
+pub(crate) fn expect_number(function: &str, value: &Object) -> Result<f64, NZErrors> {
+    match value {
+        Object::Number(n) => Ok(*n),
+        _ => Err(NZErrors::native(format!(
+            "{}() expects a number, got {}.",
+            function, value
+        ))),
+    }
+}
+
+pub(crate) fn expect_string(function: &str, value: &Object) -> Result<String, NZErrors> {
+    match value {
+        Object::String(s) => Ok(s.clone()),
+        _ => Err(NZErrors::native(format!(
+            "{}() expects a string, got {}.",
+            function, value
+        ))),
+    }
+}
+
+pub(crate) fn expect_list(function: &str, value: &Object) -> Result<ListRef, NZErrors> {
+    match value {
+        Object::List(list) => Ok(Rc::clone(list)),
+        _ => Err(NZErrors::native(format!(
+            "{}() expects a list, got {}.",
+            function, value
+        ))),
+    }
+}
+
+pub(crate) fn expect_map(function: &str, value: &Object) -> Result<MapRef, NZErrors> {
+    match value {
+        Object::Map(map) => Ok(Rc::clone(map)),
+        _ => Err(NZErrors::native(format!(
+            "{}() expects a map, got {}.",
+            function, value
+        ))),
+    }
+}
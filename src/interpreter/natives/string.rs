@@ -0,0 +1,181 @@
+use std::{cell::RefCell, rc::Rc};
+
+use crate::{
+    error::NZErrors,
+    interpreter::{operators, Interpreter},
+    object::Object,
+};
+
+use super::{super::loxcallable::Arity, expect_list, expect_number, expect_string, index};
+
+/// The character at `at`, as a one-character string.
+pub fn char_at(s: &str, at: usize) -> Object {
+    Object::String(s.chars().nth(at).map(String::from).unwrap_or_default())
+}
+
+fn string(value: String) -> Object {
+    Object::String(value)
+}
+
+fn list(items: Vec<Object>) -> Object {
+    Object::List(Rc::new(RefCell::new(items)))
+}
+
+// positions count characters, so they agree with len() and indexing
+fn char_position(s: &str, byte: usize) -> Object {
+    Object::Number(s[..byte].chars().count() as f64)
+}
+
+pub fn define(interpreter: &mut Interpreter) {
+    // substring(s, start, end?) is the characters in [start, end)
+    interpreter.define_native_method("substring", Arity::Range(2, 3), |_, args| {
+        let s = expect_string("substring", &args[0])?;
+        let len = s.chars().count();
+        let start = index("String", &args[1], len, true).map_err(NZErrors::native)?;
+        let end = match args.get(2) {
+            Some(end) => index("String", end, len, true).map_err(NZErrors::native)?,
+            None => len,
+        };
+        if start > end {
+            return Err(NZErrors::native(format!(
+                "substring() start {} is after end {}.",
+                start, end
+            )));
+        }
+        Ok(string(s.chars().skip(start).take(end - start).collect()))
+    });
+
+    // indexOf and contains also search lists, comparing with `==`
+    interpreter.define_native_method("indexOf", Arity::Fixed(2), |_, args| match &args[0] {
+        Object::String(s) => {
+            let needle = expect_string("indexOf", &args[1])?;
+            Ok(match s.find(needle.as_str()) {
+                Some(byte) => char_position(s, byte),
+                None => Object::Number(-1.0),
+            })
+        }
+        Object::List(items) => {
+            let found = items
+                .borrow()
+                .iter()
+                .position(|item| operators::is_equal(item, &args[1]));
+            Ok(Object::Number(found.map_or(-1.0, |at| at as f64)))
+        }
+        other => Err(NZErrors::native(format!(
+            "indexOf() expects a string or list, got {}.",
+            other
+        ))),
+    });
+
+    interpreter.define_native_method("contains", Arity::Fixed(2), |_, args| match &args[0] {
+        Object::String(s) => {
+            let needle = expect_string("contains", &args[1])?;
+            Ok(Object::Boolean(s.contains(needle.as_str())))
+        }
+        Object::List(items) => Ok(Object::Boolean(
+            items
+                .borrow()
+                .iter()
+                .any(|item| operators::is_equal(item, &args[1])),
+        )),
+        other => Err(NZErrors::native(format!(
+            "contains() expects a string or list, got {}.",
+            other
+        ))),
+    });
+
+    interpreter.define_native_method("startsWith", Arity::Fixed(2), |_, args| {
+        let s = expect_string("startsWith", &args[0])?;
+        let prefix = expect_string("startsWith", &args[1])?;
+        Ok(Object::Boolean(s.starts_with(prefix.as_str())))
+    });
+
+    interpreter.define_native_method("endsWith", Arity::Fixed(2), |_, args| {
+        let s = expect_string("endsWith", &args[0])?;
+        let suffix = expect_string("endsWith", &args[1])?;
+        Ok(Object::Boolean(s.ends_with(suffix.as_str())))
+    });
+
+    // an empty separator splits into single characters
+    interpreter.define_native_method("split", Arity::Fixed(2), |_, args| {
+        let s = expect_string("split", &args[0])?;
+        let separator = expect_string("split", &args[1])?;
+        let parts = if separator.is_empty() {
+            s.chars().map(|c| string(c.to_string())).collect()
+        } else {
+            s.split(separator.as_str())
+                .map(|part| string(part.to_string()))
+                .collect()
+        };
+        Ok(list(parts))
+    });
+
+    // join(list, separator) prints each item the way `print` would
+    interpreter.define_native_method("join", Arity::Fixed(2), |_, args| {
+        let items = expect_list("join", &args[0])?;
+        let separator = expect_string("join", &args[1])?;
+        let parts: Vec<String> = items.borrow().iter().map(Object::to_string).collect();
+        Ok(string(parts.join(&separator)))
+    });
+
+    interpreter.define_native_method("trim", Arity::Fixed(1), |_, args| {
+        Ok(string(expect_string("trim", &args[0])?.trim().to_string()))
+    });
+
+    interpreter.define_native_method("upper", Arity::Fixed(1), |_, args| {
+        Ok(string(expect_string("upper", &args[0])?.to_uppercase()))
+    });
+
+    interpreter.define_native_method("lower", Arity::Fixed(1), |_, args| {
+        Ok(string(expect_string("lower", &args[0])?.to_lowercase()))
+    });
+
+    // replaces every occurrence
+    interpreter.define_native_method("replace", Arity::Fixed(3), |_, args| {
+        let s = expect_string("replace", &args[0])?;
+        let from = expect_string("replace", &args[1])?;
+        let to = expect_string("replace", &args[2])?;
+        if from.is_empty() {
+            return Err(NZErrors::native("replace() pattern must not be empty."));
+        }
+        Ok(string(s.replace(from.as_str(), &to)))
+    });
+
+    interpreter.define_native_method("repeat", Arity::Fixed(2), |_, args| {
+        let s = expect_string("repeat", &args[0])?;
+        match expect_number("repeat", &args[1])? {
+            n if n >= 0.0 && n.fract() == 0.0 => Ok(string(s.repeat(n as usize))),
+            n => Err(NZErrors::native(format!(
+                "repeat() count must be a non-negative integer, got {}.",
+                n
+            ))),
+        }
+    });
+
+    // chr(code) and ord(char) convert between characters and code points
+    interpreter.define_native("chr", Arity::Fixed(1), |_, args| {
+        let code = expect_number("chr", &args[0])?;
+        let c = (code.fract() == 0.0 && (0.0..=u32::MAX as f64).contains(&code))
+            .then(|| char::from_u32(code as u32))
+            .flatten();
+        if let Some(c) = c {
+            return Ok(string(c.to_string()));
+        }
+        Err(NZErrors::native(format!(
+            "chr() expects a Unicode code point, got {}.",
+            code
+        )))
+    });
+
+    interpreter.define_native_method("ord", Arity::Fixed(1), |_, args| {
+        let s = expect_string("ord", &args[0])?;
+        let mut chars = s.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Ok(Object::Number(c as u32 as f64)),
+            _ => Err(NZErrors::native(format!(
+                "ord() expects a single character, got \"{}\".",
+                s
+            ))),
+        }
+    });
+}
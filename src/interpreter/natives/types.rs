@@ -0,0 +1,67 @@
+use crate::{
+    error::NZErrors,
+    interpreter::{operators, Interpreter},
+    object::Object,
+};
+
+use super::super::loxcallable::Arity;
+
+/// The name `type()` reports for a value.
+pub fn type_name(value: &Object) -> &'static str {
+    match value {
+        Object::Nill => "nil",
+        Object::Boolean(_) => "bool",
+        Object::Number(_) => "number",
+        Object::String(_) => "string",
+        Object::Callable(_) => "function",
+        Object::Class(_) => "class",
+        Object::Instance(_) => "instance",
+        Object::List(_) => "list",
+        Object::Map(_) => "map",
+    }
+}
+
+pub fn define(interpreter: &mut Interpreter) {
+    interpreter.define_native("type", Arity::Fixed(1), |_, args| {
+        Ok(Object::String(type_name(&args[0]).to_string()))
+    });
+
+    // the text `print` would show
+    interpreter.define_native("str", Arity::Fixed(1), |_, args| {
+        Ok(Object::String(args[0].to_string()))
+    });
+
+    // num("12.5") parses decimal text, ignoring surrounding whitespace
+    interpreter.define_native("num", Arity::Fixed(1), |_, args| match &args[0] {
+        Object::Number(n) => Ok(Object::Number(*n)),
+        Object::String(s) => s
+            .trim()
+            .parse::<f64>()
+            .ok()
+            .filter(|n| n.is_finite())
+            .map(Object::Number)
+            .ok_or_else(|| NZErrors::native(format!("num() can't convert \"{}\" to a number.", s))),
+        other => Err(NZErrors::native(format!(
+            "num() expects a string or number, got {}.",
+            other
+        ))),
+    });
+
+    interpreter.define_native("bool", Arity::Fixed(1), |_, args| {
+        Ok(Object::Boolean(operators::is_truthy(&args[0])))
+    });
+
+    // instanceof(value, Class) is true for instances of Class or a subclass
+    interpreter.define_native("instanceof", Arity::Fixed(2), |_, args| {
+        let Object::Class(class) = &args[1] else {
+            return Err(NZErrors::native(format!(
+                "instanceof() expects a class, got {}.",
+                args[1]
+            )));
+        };
+        Ok(Object::Boolean(match &args[0] {
+            Object::Instance(instance) => instance.borrow().class().is_subclass_of(class),
+            _ => false,
+        }))
+    });
+}
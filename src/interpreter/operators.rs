@@ -0,0 +1,251 @@
+//! Semantics of the unary and binary operators, shared by the tree-walking
+//! interpreter and the bytecode VM so both backends agree on every result
+//! and error message.
+
+use std::rc::Rc;
+
+use crate::{
+    error::NZErrors,
+    object::{MapKey, Object},
+    token::{token_types::TokenType, Token},
+};
+
+use super::natives;
+
+pub fn is_truthy(obj: &Object) -> bool {
+    match obj {
+        Object::Boolean(b) => *b,
+        Object::Nill => false,
+        _ => true,
+    }
+}
+
+/// `==` is defined for every pair of values: values of different types are
+/// never equal, and classes, instances, callables, lists and maps are equal
+/// only to themselves.
+pub fn is_equal(left: &Object, right: &Object) -> bool {
+    match (left, right) {
+        (Object::Nill, Object::Nill) => true,
+        (Object::Boolean(l), Object::Boolean(r)) => l == r,
+        (Object::Number(l), Object::Number(r)) => l == r,
+        (Object::String(l), Object::String(r)) => l == r,
+        (Object::Callable(l), Object::Callable(r)) => Rc::ptr_eq(l, r),
+        (Object::Class(l), Object::Class(r)) => Rc::ptr_eq(l, r),
+        (Object::Instance(l), Object::Instance(r)) => Rc::ptr_eq(l, r),
+        (Object::List(l), Object::List(r)) => Rc::ptr_eq(l, r),
+        (Object::Map(l), Object::Map(r)) => Rc::ptr_eq(l, r),
+        _ => false,
+    }
+}
+
+pub fn unary(op: &Token, right: &Object) -> Result<Object, NZErrors> {
+    match op.token_type {
+        TokenType::MINUS => match right {
+            Object::Number(n) => Ok(Object::Number(-n)),
+            _ => Err(NZErrors::RuntimeError(
+                op.clone(),
+                "Operand must be a number for unary minus.".to_string(),
+            )),
+        },
+        TokenType::BANG => Ok(Object::Boolean(!is_truthy(right))),
+        _ => Err(NZErrors::RuntimeError(
+            op.clone(),
+            "Unknown operator.".to_string(),
+        )),
+    }
+}
+
+pub fn binary(left: &Object, op: &Token, right: &Object) -> Result<Object, NZErrors> {
+    match op.token_type {
+        TokenType::PLUS => isadd(right, left, op),
+        TokenType::MINUS => issub(right, left, op),
+        TokenType::STAR => ismul(right, left, op),
+        TokenType::SLASH => isdiv(right, left, op),
+        TokenType::PERCENT => ismod(right, left, op),
+        TokenType::TILDESLASH => isfloordiv(right, left, op),
+        TokenType::STARSTAR => ispow(right, left, op),
+        TokenType::EQUALEQUAL => Ok(Object::Boolean(is_equal(left, right))),
+        TokenType::BANGEQUAL => Ok(Object::Boolean(!is_equal(left, right))),
+        TokenType::GREATER => isgreater(right, left, op),
+        TokenType::GREATEREQUAL => isgreaterequal(right, left, op),
+        TokenType::LESS => isless(right, left, op),
+        TokenType::LESSEQUAL => islessequal(right, left, op),
+        _ => Err(NZErrors::RuntimeError(
+            op.clone(),
+            "Unsupported binary operator".to_string(),
+        )),
+    }
+}
+
+const NOT_INDEXABLE: &str = "Only lists, maps and strings can be indexed.";
+
+/// `object[index]` for lists, maps and strings; indexing a string gives the
+/// character at that position.
+pub fn get_index(object: &Object, index: &Object, bracket: &Token) -> Result<Object, NZErrors> {
+    let error = |message| NZErrors::RuntimeError(bracket.clone(), message);
+    match object {
+        Object::List(list) => {
+            let list = list.borrow();
+            let at = natives::index("List", index, list.len(), false).map_err(error)?;
+            Ok(list[at].clone())
+        }
+        Object::Map(map) => {
+            let key = MapKey::from_object(index).map_err(error)?;
+            map.borrow()
+                .get(&key)
+                .cloned()
+                .ok_or_else(|| error(format!("Undefined key '{}'.", index)))
+        }
+        Object::String(s) => {
+            let at = natives::index("String", index, s.chars().count(), false).map_err(error)?;
+            Ok(natives::string::char_at(s, at))
+        }
+        _ => Err(error(NOT_INDEXABLE.to_string())),
+    }
+}
+
+/// `object[index] = value` for lists and maps. Strings can be indexed but
+/// not assigned to.
+pub fn set_index(
+    object: &Object,
+    index: &Object,
+    value: Object,
+    bracket: &Token,
+) -> Result<(), NZErrors> {
+    let error = |message| NZErrors::RuntimeError(bracket.clone(), message);
+    match object {
+        Object::List(list) => {
+            let mut list = list.borrow_mut();
+            let at = natives::index("List", index, list.len(), false).map_err(error)?;
+            list[at] = value;
+        }
+        Object::Map(map) => {
+            let key = MapKey::from_object(index).map_err(error)?;
+            map.borrow_mut().insert(key, value);
+        }
+        Object::String(_) => return Err(error("Strings are immutable.".to_string())),
+        _ => return Err(error(NOT_INDEXABLE.to_string())),
+    }
+    Ok(())
+}
+
+fn issub(right: &Object, left: &Object, op: &Token) -> Result<Object, NZErrors> {
+    match (left, right) {
+        (Object::Number(l), Object::Number(r)) => Ok(Object::Number(l - r)),
+        _ => Err(NZErrors::RuntimeError(
+            op.clone(),
+            "Cannot subtract two different types".to_string(),
+        )),
+    }
+}
+
+fn ismul(right: &Object, left: &Object, op: &Token) -> Result<Object, NZErrors> {
+    match (left, right) {
+        (Object::Number(l), Object::Number(r)) => Ok(Object::Number(l * r)),
+        _ => Err(NZErrors::RuntimeError(
+            op.clone(),
+            "Cannot multiply two different types".to_string(),
+        )),
+    }
+}
+
+fn isdiv(right: &Object, left: &Object, op: &Token) -> Result<Object, NZErrors> {
+    match (left, right) {
+        (Object::Number(l), Object::Number(r)) => Ok(Object::Number(l / r)),
+        _ => Err(NZErrors::RuntimeError(
+            op.clone(),
+            "Cannot divide two different types".to_string(),
+        )),
+    }
+}
+
+// `%` and `~/` both round the quotient towards negative infinity, so
+// `(a ~/ b) * b + a % b == a` and the remainder takes the sign of `b`.
+fn ismod(right: &Object, left: &Object, op: &Token) -> Result<Object, NZErrors> {
+    let (l, r) = divisor_operands(right, left, op)?;
+    Ok(Object::Number(l - r * (l / r).floor()))
+}
+
+fn isfloordiv(right: &Object, left: &Object, op: &Token) -> Result<Object, NZErrors> {
+    let (l, r) = divisor_operands(right, left, op)?;
+    Ok(Object::Number((l / r).floor()))
+}
+
+// Both operands as numbers, with a divisor that isn't zero. Fractions are
+// fine: `5.5 % 2` is 1.5.
+fn divisor_operands(right: &Object, left: &Object, op: &Token) -> Result<(f64, f64), NZErrors> {
+    match (left, right) {
+        (Object::Number(_), Object::Number(r)) if *r == 0.0 => Err(NZErrors::RuntimeError(
+            op.clone(),
+            "Division by zero.".to_string(),
+        )),
+        (Object::Number(l), Object::Number(r)) => Ok((*l, *r)),
+        _ => Err(NZErrors::RuntimeError(
+            op.clone(),
+            format!("Operands of '{}' must be numbers.", op.lexeme),
+        )),
+    }
+}
+
+fn ispow(right: &Object, left: &Object, op: &Token) -> Result<Object, NZErrors> {
+    match (left, right) {
+        (Object::Number(l), Object::Number(r)) => Ok(Object::Number(l.powf(*r))),
+        _ => Err(NZErrors::RuntimeError(
+            op.clone(),
+            "Operands of '**' must be numbers.".to_string(),
+        )),
+    }
+}
+
+fn isadd(right: &Object, left: &Object, op: &Token) -> Result<Object, NZErrors> {
+    match (left, right) {
+        (Object::Number(l), Object::Number(r)) => Ok(Object::Number(l + r)),
+        (Object::String(l), Object::String(r)) => Ok(Object::String(format!("{}{}", l, r))),
+        (Object::Number(l), Object::String(r)) => Ok(Object::String(format!("{}{}", l, r))),
+        (Object::String(l), Object::Number(r)) => Ok(Object::String(format!("{}{}", l, r))),
+        _ => Err(NZErrors::RuntimeError(
+            op.clone(),
+            "Cannot add two different types".to_string(),
+        )),
+    }
+}
+
+fn isgreaterequal(right: &Object, left: &Object, op: &Token) -> Result<Object, NZErrors> {
+    match (left, right) {
+        (Object::Number(l), Object::Number(r)) => Ok(Object::Boolean(l >= r)),
+        _ => Err(NZErrors::RuntimeError(
+            op.clone(),
+            "Cannot compare two different types".to_string(),
+        )),
+    }
+}
+
+fn islessequal(right: &Object, left: &Object, op: &Token) -> Result<Object, NZErrors> {
+    match (left, right) {
+        (Object::Number(l), Object::Number(r)) => Ok(Object::Boolean(l <= r)),
+        _ => Err(NZErrors::RuntimeError(
+            op.clone(),
+            "Cannot compare two different types".to_string(),
+        )),
+    }
+}
+
+fn isless(right: &Object, left: &Object, op: &Token) -> Result<Object, NZErrors> {
+    match (left, right) {
+        (Object::Number(l), Object::Number(r)) => Ok(Object::Boolean(l < r)),
+        _ => Err(NZErrors::RuntimeError(
+            op.clone(),
+            "Cannot compare two different types".to_string(),
+        )),
+    }
+}
+
+fn isgreater(right: &Object, left: &Object, op: &Token) -> Result<Object, NZErrors> {
+    match (left, right) {
+        (Object::Number(l), Object::Number(r)) => Ok(Object::Boolean(l > r)),
+        _ => Err(NZErrors::RuntimeError(
+            op.clone(),
+            "Cannot compare two different types".to_string(),
+        )),
+    }
+}
@@ -0,0 +1,28 @@
+//! Scanner, parser, resolver and interpreters for `.nz` scripts.
+//!
+//! Embedders create an [`Interpreter`], hand it source with
+//! [`Interpreter::run`] or [`Interpreter::eval`], exchange values with the
+//! script through [`Interpreter::get_global`] and [`Interpreter::set_global`],
+//! and expose Rust closures with [`Interpreter::define_native`] (or any
+//! [`LoxCallable`](interpreter::loxcallable::LoxCallable) with
+//! [`Interpreter::define_callable`]). Errors come back as [`NZErrors`]; call
+//! [`NZErrors::diagnostic`] with the source to render them.
+//!
+//! The [`vm`] module is an alternative backend: [`vm::Compiler`] turns the
+//! resolved statements into bytecode and [`vm::Vm`] runs it against the same
+//! interpreter's globals and natives.
+
+pub mod ast;
+pub mod environment;
+pub mod error;
+pub mod interpreter;
+pub mod object;
+pub mod parser;
+pub mod resolver;
+pub mod scanner;
+pub mod token;
+pub mod vm;
+
+pub use error::{Diagnostic, ErrorKind, NZErrors};
+pub use interpreter::Interpreter;
+pub use object::Object;
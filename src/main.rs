@@ -1,54 +1,230 @@
-mod ast;
-mod environment;
-mod error;
-mod interpreter;
-mod object;
-mod parser;
-mod scanner;
-mod token;
+use std::{
+    env::args,
+    fs::read_to_string,
+    io::{stdin, stdout, Write},
+    process::exit,
+    thread,
+};
 
-use std::{env::args, fs::read_to_string};
+use interpreter::{
+    ast, parser,
+    resolver::Resolver,
+    scanner,
+    token::{token_types::TokenType, Token},
+    vm::{Compiler, Vm},
+    ErrorKind, Interpreter, NZErrors,
+};
 
-use error::NZErrors;
-use interpreter::Interpreter;
+// The tree-walker recurses on the native stack, a few kilobytes per call in
+// debug builds; this leaves room for `MAX_CALL_DEPTH` calls. Only the pages
+// actually used are committed.
+const STACK_SIZE: usize = 256 * 1024 * 1024;
+
+const USAGE: &str =
+    "Usage: interpreter [--vm] [--dump-source] [--dump-tokens] [--dump-ast] [file.nz]";
+
+/// `vm` selects the bytecode backend instead of the tree-walker. The dumps
+/// are debug output of the individual stages, printed to stderr so the
+/// script's own stdout stays clean.
+#[derive(Default)]
+struct Options {
+    vm: bool,
+    dump_source: bool,
+    dump_tokens: bool,
+    dump_ast: bool,
+}
 
 fn main() {
-    let args = args().collect::<Vec<String>>();
-    if args.len() < 2 {
-        eprintln!("Usage: cargo run -- (.nz file)");
+    let run = thread::Builder::new()
+        .stack_size(STACK_SIZE)
+        .spawn(run)
+        .unwrap_or_else(|e| fail(&[NZErrors::FileReadError(e.to_string())], ""));
+    // a panic has already been reported by the thread itself
+    if run.join().is_err() {
+        exit(101);
+    }
+}
+
+fn run() {
+    let mut options = Options::default();
+    let mut path = None;
+    for arg in args().skip(1) {
+        match arg.as_str() {
+            "--vm" => options.vm = true,
+            "--dump-source" => options.dump_source = true,
+            "--dump-tokens" => options.dump_tokens = true,
+            "--dump-ast" => options.dump_ast = true,
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return;
+            }
+            _ if arg.starts_with('-') || path.is_some() => {
+                eprintln!("Unexpected argument '{}'.\n{}", arg, USAGE);
+                exit(64);
+            }
+            _ => path = Some(arg),
+        }
+    }
+
+    let Some(path) = path else {
+        run_prompt(&options);
         return;
+    };
+    let buffer = read_file(&path).unwrap_or_else(|e| fail(&[e], ""));
+    if options.dump_source {
+        eprintln!("Content: {:?}", buffer);
     }
-    let buffer = read_file(&args[1]).map_err(|e| e.report_error()).unwrap();
-    println!("Content: {:?}", buffer);
 
-    let tokens = scanner::Scanner::new(buffer)
+    let tokens = scanner::Scanner::new(buffer.clone())
         .scan_tokens()
-        .map_err(|e| e.report_error())
-        .unwrap();
-    println!("Tokens: {:#?}", tokens);
+        .unwrap_or_else(|e| fail(&[e], &buffer));
+    if options.dump_tokens {
+        eprintln!("Tokens: {:#?}", tokens);
+    }
 
     let mut parser = parser::Parser::new(tokens);
-    let expr = parser.parse().map_err(|e| e.report_error()).unwrap();
-    println!("Expr: {:#?}", expr);
+    let expr = parser
+        .parse()
+        .unwrap_or_else(|errors| fail(&errors, &buffer));
+    if options.dump_ast {
+        eprintln!("Expr: {:#?}", expr);
+    }
 
     let mut interpreter = Interpreter::new();
-    interpreter
-        .interpret(&expr)
-        .map_err(|e| e.report_error())
-        .unwrap();
+    Resolver::new(&mut interpreter)
+        .resolve(&expr)
+        .unwrap_or_else(|e| fail(&[e], &buffer));
+    if options.vm {
+        let script = Compiler::compile(&expr).unwrap_or_else(|e| fail(&[e], &buffer));
+        Vm::new(&mut interpreter)
+            .interpret(script)
+            .unwrap_or_else(|e| fail(&[e], &buffer));
+    } else {
+        interpreter
+            .interpret(&expr)
+            .unwrap_or_else(|e| fail(&[e], &buffer));
+    }
+}
 
-    // let mut asd = ast_print::AstPrint::new();
-    // println!("Ast: {}", asd.print(&expr));
+fn read_file(path: &str) -> Result<String, NZErrors> {
+    read_to_string(path).map_err(|e| NZErrors::FileReadError(e.to_string()))
+}
 
-    // ast::ast_generator::AstGenerator::define_ast(
-    //     "/home/tirth/code/interpreter/src/",
-    //     "ast",
-    //     &["Expr"],
-    // )
-    // .expect("Ast Generator Error");
+fn report(errors: &[NZErrors], source: &str) {
+    for error in errors {
+        eprintln!("{}", error.diagnostic(source));
+    }
 }
 
-fn read_file(path: &str) -> Result<String, NZErrors> {
-    let buffer = read_to_string(path).map_err(|e| NZErrors::FileReadError(e.to_string()));
-    buffer
+/// Reports the errors and ends the process. Runtime errors exit with 69,
+/// everything caught before the program starts running exits with 1.
+fn fail(errors: &[NZErrors], source: &str) -> ! {
+    report(errors, source);
+    match errors.first().map(NZErrors::kind) {
+        Some(ErrorKind::Runtime) => exit(69),
+        _ => exit(1),
+    }
+}
+
+/// Read-eval-print loop. One interpreter lives for the whole session, so
+/// globals defined on one line are visible on the next.
+fn run_prompt(options: &Options) {
+    let mut interpreter = Interpreter::new();
+    let mut buffer = String::new();
+
+    loop {
+        print!("{}", if buffer.is_empty() { "> " } else { "... " });
+        stdout().flush().ok();
+
+        let mut line = String::new();
+        match stdin().read_line(&mut line) {
+            Ok(0) => {
+                println!();
+                break;
+            }
+            Ok(_) => buffer.push_str(&line),
+            Err(e) => {
+                report(&[NZErrors::FileReadError(e.to_string())], "");
+                break;
+            }
+        }
+
+        let tokens = match scanner::Scanner::new(buffer.clone()).scan_tokens() {
+            Ok(tokens) => tokens,
+            Err(e) => {
+                report(&[e], &buffer);
+                buffer.clear();
+                continue;
+            }
+        };
+
+        // keep reading while a block, function or class body is still open
+        if brace_depth(&tokens) > 0 {
+            continue;
+        }
+        if options.dump_source {
+            eprintln!("Content: {:?}", buffer);
+        }
+        if options.dump_tokens {
+            eprintln!("Tokens: {:#?}", tokens);
+        }
+        if let Err(errors) = run_line(&mut interpreter, tokens, options) {
+            report(&errors, &buffer);
+        }
+        buffer.clear();
+    }
+}
+
+fn run_line(
+    interpreter: &mut Interpreter,
+    tokens: Vec<Token>,
+    options: &Options,
+) -> Result<(), Vec<NZErrors>> {
+    // a bare expression is evaluated and its value echoed back
+    let statements = match parser::Parser::new(tokens.clone()).parse_expression() {
+        Ok(expression) => vec![ast::Stmt::Expression { expression }],
+        Err(_) => parser::Parser::new(tokens).parse()?,
+    };
+    if options.dump_ast {
+        eprintln!("Expr: {:#?}", statements);
+    }
+    Resolver::new(interpreter)
+        .resolve(&statements)
+        .map_err(|e| vec![e])?;
+
+    for stmt in &statements {
+        match stmt {
+            ast::Stmt::Expression { expression } if options.vm => {
+                let script = Compiler::compile_expression(expression).map_err(|e| vec![e])?;
+                let value = Vm::new(interpreter)
+                    .interpret(script)
+                    .map_err(|e| vec![e])?;
+                println!("{}", value);
+            }
+            ast::Stmt::Expression { expression } => {
+                let value = interpreter.evaluate(expression).map_err(|e| vec![e])?;
+                println!("{}", value);
+            }
+            _ if options.vm => {
+                let script = Compiler::compile(std::slice::from_ref(stmt)).map_err(|e| vec![e])?;
+                Vm::new(interpreter)
+                    .interpret(script)
+                    .map_err(|e| vec![e])?;
+            }
+            _ => interpreter
+                .interpret(std::slice::from_ref(stmt))
+                .map_err(|e| vec![e])?,
+        }
+    }
+    Ok(())
+}
+
+fn brace_depth(tokens: &[Token]) -> i32 {
+    tokens
+        .iter()
+        .fold(0, |depth, token| match token.token_type {
+            TokenType::LEFTBRACE => depth + 1,
+            TokenType::RIGHTBRACE => depth - 1,
+            _ => depth,
+        })
 }
@@ -1,8 +1,15 @@
 use core::fmt;
-use std::{cell::RefCell, rc::Rc};
+use std::{cell::RefCell, cmp::Ordering, collections::BTreeMap, rc::Rc};
 
 use crate::interpreter::{loxcallable::LoxCallable, loxclass::LoxClass, loxinstance::LoxInstance};
 
+/// A list value; copies of it share and mutate the same elements.
+pub type ListRef = Rc<RefCell<Vec<Object>>>;
+
+/// A map value, shared the same way as `ListRef`. Entries are kept ordered
+/// by key so printing and `keys()` are deterministic.
+pub type MapRef = Rc<RefCell<BTreeMap<MapKey, Object>>>;
+
 #[derive(Clone, Debug)]
 pub enum Object {
     Number(f64),
@@ -11,9 +18,111 @@ pub enum Object {
     Callable(Rc<dyn LoxCallable>),
     Class(Rc<LoxClass>),
     Instance(Rc<RefCell<LoxInstance>>),
+    // shared: every copy of the value sees pushes and index assignments
+    List(ListRef),
+    Map(MapRef),
     Nill,
 }
 
+/// The values that can be used as map keys. Two keys are the same if the
+/// values are equal: strings by content, numbers numerically (so `0` and
+/// `-0` match), booleans by value, and `nil` only with itself.
+#[derive(Clone, Debug)]
+pub enum MapKey {
+    Nil,
+    Boolean(bool),
+    Number(f64),
+    String(String),
+}
+
+impl MapKey {
+    pub fn from_object(value: &Object) -> Result<MapKey, String> {
+        match value {
+            Object::Nill => Ok(MapKey::Nil),
+            Object::Boolean(b) => Ok(MapKey::Boolean(*b)),
+            Object::Number(n) if n.is_nan() => Err("NaN can't be used as a map key.".to_string()),
+            // adding 0.0 turns -0.0 into 0.0
+            Object::Number(n) => Ok(MapKey::Number(n + 0.0)),
+            Object::String(s) => Ok(MapKey::String(s.clone())),
+            other => Err(format!(
+                "Only strings, numbers, booleans and nil can be map keys, got {}.",
+                other
+            )),
+        }
+    }
+
+    pub fn to_object(&self) -> Object {
+        match self {
+            MapKey::Nil => Object::Nill,
+            MapKey::Boolean(b) => Object::Boolean(*b),
+            MapKey::Number(n) => Object::Number(*n),
+            MapKey::String(s) => Object::String(s.clone()),
+        }
+    }
+
+    fn rank(&self) -> u8 {
+        match self {
+            MapKey::Nil => 0,
+            MapKey::Boolean(_) => 1,
+            MapKey::Number(_) => 2,
+            MapKey::String(_) => 3,
+        }
+    }
+}
+
+// keys of different types never compare equal; within a type the natural
+// order applies (NaN is rejected by `from_object`, so numbers are total)
+impl Ord for MapKey {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (MapKey::Boolean(a), MapKey::Boolean(b)) => a.cmp(b),
+            (MapKey::Number(a), MapKey::Number(b)) => a.total_cmp(b),
+            (MapKey::String(a), MapKey::String(b)) => a.cmp(b),
+            _ => self.rank().cmp(&other.rank()),
+        }
+    }
+}
+
+impl PartialOrd for MapKey {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for MapKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for MapKey {}
+
+/// An element of a list or map as printed inside the container: strings are
+/// quoted and escaped the way a literal writes them, so `["1"]` and `[1]`
+/// print differently. Everything else prints as usual.
+struct Element<'a>(&'a Object);
+
+impl fmt::Display for Element<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let Object::String(s) = self.0 else {
+            return write!(f, "{}", self.0);
+        };
+        write!(f, "\"")?;
+        for c in s.chars() {
+            match c {
+                '"' | '\\' | '$' => write!(f, "\\{}", c)?,
+                '\n' => write!(f, "\\n")?,
+                '\t' => write!(f, "\\t")?,
+                '\r' => write!(f, "\\r")?,
+                '\0' => write!(f, "\\0")?,
+                c if c.is_control() => write!(f, "\\u{{{:x}}}", c as u32)?,
+                c => write!(f, "{}", c)?,
+            }
+        }
+        write!(f, "\"")
+    }
+}
+
 impl fmt::Display for Object {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
@@ -24,6 +133,21 @@ impl fmt::Display for Object {
             Object::Callable(c) => write!(f, "{}", c),
             Object::Instance(i) => write!(f, "{}", i.borrow()),
             Object::Class(c) => write!(f, "{}", c),
+            Object::List(l) => {
+                let items: Vec<String> =
+                    l.borrow().iter().map(|o| Element(o).to_string()).collect();
+                write!(f, "[{}]", items.join(", "))
+            }
+            Object::Map(m) => {
+                let entries: Vec<String> = m
+                    .borrow()
+                    .iter()
+                    .map(|(key, value)| {
+                        format!("{}: {}", Element(&key.to_object()), Element(value))
+                    })
+                    .collect();
+                write!(f, "{{{}}}", entries.join(", "))
+            }
         }
     }
 }
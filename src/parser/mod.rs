@@ -1,45 +1,161 @@
+use std::collections::HashMap;
+
 use crate::{
     ast::{Expr, Stmt},
     error::NZErrors,
-    token::{token_types::TokenType, Literal, Token},
+    token::{token_types::TokenType, Literal, Span, Token},
 };
 
+// Statements, expressions and function bodies nested inside one another.
+// Every level takes native stack here and in the passes after the parser, so
+// absurdly deep input is rejected up front instead of overflowing it.
+const MAX_NESTING: usize = 256;
+
 pub struct Parser {
     tokens: Vec<Token>,
     current: usize,
+    errors: Vec<NZErrors>,
+    // loops enclosing the current statement within the current function
+    loop_depth: usize,
+    // levels of nesting around the token being parsed, see `MAX_NESTING`
+    nesting: usize,
+    // set once `MAX_NESTING` is hit; the rest of the input is skipped then
+    too_deep: bool,
+    // `///` comments by the id of the token that follows them
+    docs: HashMap<usize, String>,
 }
 impl Parser {
     pub fn new(tokens: Vec<Token>) -> Self {
-        Self { tokens, current: 0 }
+        // doc comments are taken out of the token stream, so one in an odd
+        // place is simply ignored rather than a syntax error
+        let mut docs = HashMap::new();
+        let mut lines = Vec::new();
+        let mut code = Vec::with_capacity(tokens.len());
+        for token in tokens {
+            match (token.token_type, token.literal) {
+                (TokenType::DOC, Literal::String(text)) => lines.push(text),
+                (token_type, literal) => {
+                    if !lines.is_empty() {
+                        docs.insert(token.id, lines.join("\n"));
+                        lines.clear();
+                    }
+                    code.push(Token {
+                        token_type,
+                        literal,
+                        ..token
+                    });
+                }
+            }
+        }
+        Self {
+            tokens: code,
+            current: 0,
+            errors: Vec::new(),
+            loop_depth: 0,
+            nesting: 0,
+            too_deep: false,
+            docs,
+        }
     }
 
-    pub fn parse(&mut self) -> Result<Vec<Stmt>, NZErrors> {
+    /// Parses the whole program. On a syntax error the parser skips ahead to
+    /// the next statement and keeps going, so every error in the file is
+    /// returned at once.
+    pub fn parse(&mut self) -> Result<Vec<Stmt>, Vec<NZErrors>> {
         let mut statements = Vec::new();
 
         while !self.is_at_end() {
-            statements.push(self.declaration()?);
+            if let Some(stmt) = self.declaration() {
+                statements.push(stmt);
+            }
         }
 
-        Ok(statements)
+        if self.errors.is_empty() {
+            Ok(statements)
+        } else {
+            Err(std::mem::take(&mut self.errors))
+        }
+    }
+
+    /// Parses the whole input as a single expression, the way a bare line is
+    /// typed at the REPL without a trailing ';'.
+    pub fn parse_expression(&mut self) -> Result<Expr, NZErrors> {
+        let expr = self.expression()?;
+        if !self.is_at_end() {
+            return Err(NZErrors::ParseError(
+                self.peek(),
+                "Expect end of expression.".to_string(),
+            ));
+        }
+        Ok(expr)
     }
 
-    fn declaration(&mut self) -> Result<Stmt, NZErrors> {
+    /// Records the error and resynchronizes instead of failing, so callers
+    /// only see `None` for a declaration that could not be parsed.
+    fn declaration(&mut self) -> Option<Stmt> {
+        match self.try_declaration() {
+            Ok(stmt) => Some(stmt),
+            Err(error) => {
+                // errors from unwinding out of skipped input say nothing new
+                if !self.too_deep {
+                    self.errors.push(error);
+                }
+                self.synchronize();
+                None
+            }
+        }
+    }
+
+    fn try_declaration(&mut self) -> Result<Stmt, NZErrors> {
         // Note: order matters: class, fun, var before statement
         if self.match_token(&[TokenType::CLASS]) {
-            return self.class_declaration();
+            let doc = self.docs.remove(&self.previous().id);
+            return self.class_declaration(doc);
         }
-        if self.match_token(&[TokenType::FUN]) {
-            return self.function("function");
+        // `fun (` starts a function expression instead
+        if self.check(&TokenType::FUN) && self.check_at(1, &TokenType::IDENTIFIER) {
+            let keyword = self.next();
+            let doc = self.docs.remove(&keyword.id);
+            return self.function("function", doc);
         }
         if self.match_token(&[TokenType::VAR]) {
             return self.var_declaration();
         }
 
-        // just return statement directly, no synchronize
         self.statement()
     }
 
-    fn class_declaration(&mut self) -> Result<Stmt, NZErrors> {
+    /// Discards tokens until the start of the next statement: just past a
+    /// ';' or right before a keyword that begins a declaration or statement.
+    fn synchronize(&mut self) {
+        if !self.is_at_end() {
+            self.next();
+        }
+
+        while !self.is_at_end() {
+            if self.previous().token_type == TokenType::SEMICOLON {
+                return;
+            }
+
+            match self.peek().token_type {
+                TokenType::CLASS
+                | TokenType::FUN
+                | TokenType::VAR
+                | TokenType::FOR
+                | TokenType::IF
+                | TokenType::WHILE
+                | TokenType::PRINT
+                | TokenType::RETURN
+                | TokenType::BREAK
+                | TokenType::CONTINUE => return,
+                _ => {}
+            }
+
+            self.next();
+        }
+    }
+
+    fn class_declaration(&mut self, doc: Option<String>) -> Result<Stmt, NZErrors> {
         let name = self.consume(TokenType::IDENTIFIER, "Expect class name.")?;
         let superclass = if self.match_token(&[TokenType::LESS]) {
             self.consume(TokenType::IDENTIFIER, "Expect superclass name.")?;
@@ -50,23 +166,36 @@ impl Parser {
         self.consume(TokenType::LEFTBRACE, "Expect '{' before class body.")?;
         let mut methods = Vec::new();
         while !self.check(&TokenType::RIGHTBRACE) && !self.is_at_end() {
-            methods.push(self.function("method")?);
+            let doc = self.docs.remove(&self.peek().id);
+            methods.push(self.function("method", doc)?);
         }
         self.consume(TokenType::RIGHTBRACE, "Expect '}' after class body.")?;
         Ok(Stmt::Class {
             name,
             superclass,
             methods,
+            doc,
         })
     }
 
-    fn function(&mut self, kind: &str) -> Result<Stmt, NZErrors> {
+    fn function(&mut self, kind: &str, doc: Option<String>) -> Result<Stmt, NZErrors> {
         let name = self.consume(TokenType::IDENTIFIER, &format!("Expect {} name.", kind))?;
         self.consume(
             TokenType::LEFTPAREN,
             &format!("Expect '(' after {} name.", kind),
         )?;
+        let parameters = self.parameters()?;
+        let body = self.function_body(kind)?;
+        Ok(Stmt::Function {
+            name,
+            params: parameters,
+            body,
+            doc,
+        })
+    }
 
+    /// Parses a parameter list after its '(' up to and including the ')'.
+    fn parameters(&mut self) -> Result<Vec<Token>, NZErrors> {
         let mut parameters = Vec::new();
         if !self.check(&TokenType::RIGHTPAREN) {
             loop {
@@ -82,18 +211,20 @@ impl Parser {
                 }
             }
         }
-
         self.consume(TokenType::RIGHTPAREN, "Expect ')' after parameters.")?;
+        Ok(parameters)
+    }
+
+    fn function_body(&mut self, kind: &str) -> Result<Vec<Stmt>, NZErrors> {
         self.consume(
             TokenType::LEFTBRACE,
             &format!("Expect '{{' before {} body.", kind),
         )?;
-        let body = self.block()?;
-        Ok(Stmt::Function {
-            name,
-            params: parameters,
-            body,
-        })
+        // a loop around the declaration does not make its body a loop
+        let enclosing_loops = std::mem::replace(&mut self.loop_depth, 0);
+        let body = self.nested(Self::block);
+        self.loop_depth = enclosing_loops;
+        body
     }
 
     fn var_declaration(&mut self) -> Result<Stmt, NZErrors> {
@@ -110,6 +241,10 @@ impl Parser {
     }
 
     fn statement(&mut self) -> Result<Stmt, NZErrors> {
+        self.nested(Self::try_statement)
+    }
+
+    fn try_statement(&mut self) -> Result<Stmt, NZErrors> {
         if self.match_token(&[TokenType::PRINT]) {
             return self.print_statement();
         }
@@ -130,6 +265,9 @@ impl Parser {
         if self.match_token(&[TokenType::RETURN]) {
             return self.return_statement();
         }
+        if self.match_token(&[TokenType::BREAK, TokenType::CONTINUE]) {
+            return self.loop_control_statement();
+        }
 
         self.expression_statement()
     }
@@ -145,7 +283,36 @@ impl Parser {
         Ok(Stmt::Return { keyword, value })
     }
 
+    fn loop_control_statement(&mut self) -> Result<Stmt, NZErrors> {
+        let keyword = self.previous();
+        self.consume(
+            TokenType::SEMICOLON,
+            &format!("Expect ';' after '{}'.", keyword.lexeme),
+        )?;
+        if self.loop_depth == 0 {
+            // the statement itself parsed fine, so report without resynchronizing
+            self.errors.push(NZErrors::ParseError(
+                keyword.clone(),
+                format!("Can't use '{}' outside of a loop.", keyword.lexeme),
+            ));
+        }
+        Ok(if keyword.token_type == TokenType::BREAK {
+            Stmt::Break { keyword }
+        } else {
+            Stmt::Continue { keyword }
+        })
+    }
+
+    /// Parses a loop body, where `break` and `continue` are allowed.
+    fn loop_body(&mut self) -> Result<Stmt, NZErrors> {
+        self.loop_depth += 1;
+        let body = self.statement();
+        self.loop_depth -= 1;
+        body
+    }
+
     fn for_statement(&mut self) -> Result<Stmt, NZErrors> {
+        let keyword = self.previous();
         self.consume(TokenType::LEFTPAREN, "Expect '(' after 'for'.")?;
 
         let initializer = if self.match_token(&[TokenType::SEMICOLON]) {
@@ -172,23 +339,17 @@ impl Parser {
 
         self.consume(TokenType::RIGHTPAREN, "Expect ')' after for clauses.")?;
 
-        let mut body = self.statement()?;
-
-        // desugar increment
-        if let Some(inc) = increment {
-            body = Stmt::Block {
-                statements: vec![body, Stmt::Expression { expression: inc }],
-            };
-        }
+        let mut body = self.loop_body()?;
 
         // default condition true if omitted
         if condition.is_none() {
-            condition = Some(Expr::Literal(Literal::Boolean(true)));
+            condition = Some(Expr::Literal(Literal::Boolean(true), keyword.span));
         }
 
         body = Stmt::While {
             condition: condition.unwrap(),
             body: Box::new(body),
+            increment,
         };
 
         if let Some(init) = initializer {
@@ -204,10 +365,11 @@ impl Parser {
         self.consume(TokenType::LEFTPAREN, "Expect '(' after 'while'.")?;
         let condition = self.expression()?;
         self.consume(TokenType::RIGHTPAREN, "Expect ')' after condition.")?;
-        let body = self.statement()?;
+        let body = self.loop_body()?;
         Ok(Stmt::While {
             condition,
             body: Box::new(body),
+            increment: None,
         })
     }
 
@@ -231,7 +393,9 @@ impl Parser {
     fn block(&mut self) -> Result<Vec<Stmt>, NZErrors> {
         let mut statements = Vec::new();
         while !self.check(&TokenType::RIGHTBRACE) && !self.is_at_end() {
-            statements.push(self.declaration()?);
+            if let Some(stmt) = self.declaration() {
+                statements.push(stmt);
+            }
         }
         self.consume(TokenType::RIGHTBRACE, "Expect '}' after block.")?;
         Ok(statements)
@@ -250,7 +414,7 @@ impl Parser {
     }
 
     fn expression(&mut self) -> Result<Expr, NZErrors> {
-        self.assignment()
+        self.nested(Self::assignment)
     }
 
     fn assignment(&mut self) -> Result<Expr, NZErrors> {
@@ -258,18 +422,60 @@ impl Parser {
 
         if self.match_token(&[TokenType::EQUAL]) {
             let equals = self.previous();
-            let value = self.assignment()?;
+            let value = self.expression()?;
 
             if let Expr::Variable(name) = expr {
                 return Ok(Expr::Assign(name, Box::new(value)));
             } else if let Expr::Get(object, property) = expr {
                 return Ok(Expr::Set(object, property, Box::new(value)));
+            } else if let Expr::Index(object, bracket, index) = expr {
+                return Ok(Expr::IndexSet(object, bracket, index, Box::new(value)));
             }
 
-            return Err(NZErrors::ParseError(
-                equals,
-                "Invalid assignment target.".to_string(),
-            ));
+            let span = expr.span();
+            return Err(
+                NZErrors::ParseError(equals, "Invalid assignment target.".to_string())
+                    .with_span(span),
+            );
+        }
+
+        if self.match_token(&[
+            TokenType::PLUSEQUAL,
+            TokenType::MINUSEQUAL,
+            TokenType::STAREQUAL,
+            TokenType::SLASHEQUAL,
+        ]) {
+            let equals = self.previous();
+            let value = self.expression()?;
+
+            // `a += b` applies the plain binary operator; the token keeps the
+            // compound lexeme so errors point at it.
+            let mut operator = equals.clone();
+            operator.token_type = match equals.token_type {
+                TokenType::PLUSEQUAL => TokenType::PLUS,
+                TokenType::MINUSEQUAL => TokenType::MINUS,
+                TokenType::STAREQUAL => TokenType::STAR,
+                _ => TokenType::SLASH,
+            };
+
+            if let Expr::Variable(name) = expr {
+                let current = Expr::Variable(name.clone());
+                let value = Expr::Binary(Box::new(current), operator, Box::new(value));
+                return Ok(Expr::Assign(name, Box::new(value)));
+            } else if let Expr::Get(object, property) = expr {
+                return Ok(Expr::CompoundSet(
+                    object,
+                    property,
+                    operator,
+                    Box::new(value),
+                ));
+            }
+
+            let span = expr.span();
+            return Err(
+                NZErrors::ParseError(equals, "Invalid assignment target.".to_string())
+                    .with_span(span),
+            );
         }
 
         Ok(expr)
@@ -339,7 +545,12 @@ impl Parser {
     fn factor(&mut self) -> Result<Expr, NZErrors> {
         let mut expr = self.unary()?;
 
-        while self.match_token(&[TokenType::SLASH, TokenType::STAR]) {
+        while self.match_token(&[
+            TokenType::SLASH,
+            TokenType::STAR,
+            TokenType::PERCENT,
+            TokenType::TILDESLASH,
+        ]) {
             let operator = self.previous();
             let right = self.unary()?;
             expr = Expr::Binary(Box::new(expr), operator, Box::new(right));
@@ -350,10 +561,23 @@ impl Parser {
     fn unary(&mut self) -> Result<Expr, NZErrors> {
         if self.match_token(&[TokenType::BANG, TokenType::MINUS]) {
             let operator = self.previous();
-            let right = self.unary()?;
+            let right = self.nested(Self::unary)?;
             return Ok(Expr::Unary(operator, Box::new(right)));
         }
-        self.call()
+        self.power()
+    }
+
+    // The right operand goes back through unary, so `**` is right-associative,
+    // `-2 ** 2` negates the power and `2 ** -1` needs no parentheses.
+    fn power(&mut self) -> Result<Expr, NZErrors> {
+        let expr = self.call()?;
+
+        if self.match_token(&[TokenType::STARSTAR]) {
+            let operator = self.previous();
+            let right = self.nested(Self::unary)?;
+            return Ok(Expr::Binary(Box::new(expr), operator, Box::new(right)));
+        }
+        Ok(expr)
     }
 
     fn call(&mut self) -> Result<Expr, NZErrors> {
@@ -366,6 +590,10 @@ impl Parser {
                 let name =
                     self.consume(TokenType::IDENTIFIER, "Expect property name after '.'.")?;
                 expr = Expr::Get(Box::new(expr), name);
+            } else if self.match_token(&[TokenType::LEFTBRACKET]) {
+                let index = self.expression()?;
+                let bracket = self.consume(TokenType::RIGHTBRACKET, "Expect ']' after index.")?;
+                expr = Expr::Index(Box::new(expr), bracket, Box::new(index));
             } else {
                 break;
             }
@@ -396,13 +624,25 @@ impl Parser {
 
     fn primary(&mut self) -> Result<Expr, NZErrors> {
         if self.match_token(&[TokenType::FALSE]) {
-            return Ok(Expr::Literal(Literal::Boolean(false)));
+            return Ok(Expr::Literal(Literal::Boolean(false), self.previous().span));
         } else if self.match_token(&[TokenType::TRUE]) {
-            return Ok(Expr::Literal(Literal::Boolean(true)));
+            return Ok(Expr::Literal(Literal::Boolean(true), self.previous().span));
         } else if self.match_token(&[TokenType::NIL]) {
-            return Ok(Expr::Literal(Literal::Nil));
+            return Ok(Expr::Literal(Literal::Nil, self.previous().span));
         } else if self.match_token(&[TokenType::NUMBER, TokenType::STRING]) {
-            return Ok(Expr::Literal(self.previous().literal));
+            let token = self.previous();
+            return Ok(Expr::Literal(token.literal, token.span));
+        } else if self.match_token(&[TokenType::INTERPOLATION]) {
+            return self.interpolation();
+        } else if self.match_token(&[TokenType::FUN]) {
+            return self.lambda();
+        } else if self.check(&TokenType::IDENTIFIER) && self.check_at(1, &TokenType::ARROW) {
+            let param = self.next();
+            return self.arrow(param.span, vec![param]);
+        } else if self.check(&TokenType::LEFTPAREN) && self.arrow_ahead() {
+            let open = self.next();
+            let params = self.parameters()?;
+            return self.arrow(open.span, params);
         } else if self.match_token(&[TokenType::IDENTIFIER]) {
             return Ok(Expr::Variable(self.previous()));
         } else if self.match_token(&[TokenType::SUPER]) {
@@ -412,6 +652,10 @@ impl Parser {
             return Ok(Expr::Super(keyword, method));
         } else if self.match_token(&[TokenType::THIS]) {
             return Ok(Expr::This(self.previous()));
+        } else if self.match_token(&[TokenType::LEFTBRACKET]) {
+            return self.list();
+        } else if self.match_token(&[TokenType::LEFTBRACE]) {
+            return self.map();
         } else if self.match_token(&[TokenType::LEFTPAREN]) {
             let expr = self.expression()?;
             self.consume(TokenType::RIGHTPAREN, "Expect ')' after expression.")?;
@@ -424,6 +668,136 @@ impl Parser {
         ))
     }
 
+    // "a ${b} c" scans as INTERPOLATION("a ") b INTERPOLATIONEND(" c") and
+    // becomes ("a " + ${b}) + " c", where ${b} is b's printed form, so every
+    // `+` joins two strings.
+    fn interpolation(&mut self) -> Result<Expr, NZErrors> {
+        let mut part = self.previous();
+        let mut expr = Expr::Literal(part.literal.clone(), part.span);
+        loop {
+            let value = Expr::Interpolate(Box::new(self.expression()?));
+            let mut plus = part.clone();
+            plus.token_type = TokenType::PLUS;
+            expr = Expr::Binary(Box::new(expr), plus, Box::new(value));
+
+            if !self.match_token(&[TokenType::INTERPOLATION]) {
+                self.consume(
+                    TokenType::INTERPOLATIONEND,
+                    "Expect '}' after interpolated expression.",
+                )?;
+            }
+            part = self.previous();
+            let mut plus = part.clone();
+            plus.token_type = TokenType::PLUS;
+            let literal = Expr::Literal(part.literal.clone(), part.span);
+            expr = Expr::Binary(Box::new(expr), plus, Box::new(literal));
+
+            if part.token_type == TokenType::INTERPOLATIONEND {
+                return Ok(expr);
+            }
+        }
+    }
+
+    /// `fun (params) { body }`, after the `fun`.
+    fn lambda(&mut self) -> Result<Expr, NZErrors> {
+        let keyword = self.previous();
+        self.consume(TokenType::LEFTPAREN, "Expect '(' after 'fun'.")?;
+        let params = self.parameters()?;
+        let body = self.function_body("function")?;
+        let span = keyword.span.to(self.previous().span);
+        Ok(Expr::Lambda(keyword, params, body, span))
+    }
+
+    /// The rest of `x => expr` or `(a, b) => expr`, which returns `expr`.
+    fn arrow(&mut self, start: Span, params: Vec<Token>) -> Result<Expr, NZErrors> {
+        let arrow = self.consume(TokenType::ARROW, "Expect '=>' after parameters.")?;
+        let enclosing_loops = std::mem::replace(&mut self.loop_depth, 0);
+        let value = self.expression();
+        self.loop_depth = enclosing_loops;
+        let value = value?;
+        let span = start.to(value.span());
+        let body = vec![Stmt::Return {
+            keyword: arrow.clone(),
+            value: Some(value),
+        }];
+        Ok(Expr::Lambda(arrow, params, body, span))
+    }
+
+    // Whether the '(' at the current token opens the parameters of an arrow
+    // function rather than a grouping: `(`, identifiers and commas, `)` `=>`.
+    fn arrow_ahead(&self) -> bool {
+        let mut offset = 1;
+        loop {
+            if self.check_at(offset, &TokenType::RIGHTPAREN) {
+                return self.check_at(offset + 1, &TokenType::ARROW);
+            }
+            if !self.check_at(offset, &TokenType::IDENTIFIER) {
+                return false;
+            }
+            offset += 1;
+            if self.check_at(offset, &TokenType::COMMA) {
+                offset += 1;
+            }
+        }
+    }
+
+    fn list(&mut self) -> Result<Expr, NZErrors> {
+        let open = self.previous();
+        let mut elements = Vec::new();
+        if !self.check(&TokenType::RIGHTBRACKET) {
+            loop {
+                elements.push(self.expression()?);
+                // allow a trailing comma before ']'
+                if !self.match_token(&[TokenType::COMMA]) || self.check(&TokenType::RIGHTBRACKET) {
+                    break;
+                }
+            }
+        }
+        let close = self.consume(TokenType::RIGHTBRACKET, "Expect ']' after list elements.")?;
+        Ok(Expr::List(open.clone(), elements, open.span.to(close.span)))
+    }
+
+    fn map(&mut self) -> Result<Expr, NZErrors> {
+        let open = self.previous();
+        let mut entries = Vec::new();
+        if !self.check(&TokenType::RIGHTBRACE) {
+            loop {
+                let key = self.expression()?;
+                self.consume(TokenType::COLON, "Expect ':' after map key.")?;
+                entries.push((key, self.expression()?));
+                // allow a trailing comma before '}'
+                if !self.match_token(&[TokenType::COMMA]) || self.check(&TokenType::RIGHTBRACE) {
+                    break;
+                }
+            }
+        }
+        let close = self.consume(TokenType::RIGHTBRACE, "Expect '}' after map entries.")?;
+        Ok(Expr::Map(open.clone(), entries, open.span.to(close.span)))
+    }
+
+    /// Parses with `parse` one level of nesting deeper. Too deep is fatal:
+    /// the error is reported once and the rest of the input is skipped, as
+    /// every enclosing level would only fail again.
+    fn nested<T>(
+        &mut self,
+        parse: impl FnOnce(&mut Self) -> Result<T, NZErrors>,
+    ) -> Result<T, NZErrors> {
+        if self.nesting == MAX_NESTING {
+            let error = |token| NZErrors::ParseError(token, "Too much nesting.".to_string());
+            let token = self.peek();
+            if !self.too_deep {
+                self.errors.push(error(token.clone()));
+                self.too_deep = true;
+            }
+            self.current = self.tokens.len() - 1;
+            return Err(error(token));
+        }
+        self.nesting += 1;
+        let result = parse(self);
+        self.nesting -= 1;
+        result
+    }
+
     fn consume(&mut self, token_type: TokenType, message: &str) -> Result<Token, NZErrors> {
         if self.check(&token_type) {
             Ok(self.next())
@@ -455,6 +829,12 @@ impl Parser {
         }
     }
 
+    fn check_at(&self, offset: usize, expected: &TokenType) -> bool {
+        self.tokens
+            .get(self.current + offset)
+            .is_some_and(|token| token.token_type == *expected)
+    }
+
     fn peek(&self) -> Token {
         self.tokens[self.current].clone()
     }
@@ -467,3 +847,46 @@ impl Parser {
         self.tokens[self.current - 1].clone()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::Scanner;
+
+    fn parse(source: &str) -> Vec<Stmt> {
+        let tokens = Scanner::new(source.to_string()).scan_tokens().unwrap();
+        Parser::new(tokens).parse().unwrap()
+    }
+
+    #[test]
+    fn doc_comment_attaches_to_function() {
+        match &parse("/// hi\nfun f(){}")[..] {
+            [Stmt::Function { doc, .. }] => assert_eq!(doc.as_deref(), Some("hi")),
+            other => panic!("expected one function, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn doc_comment_attaches_to_class_and_method() {
+        match &parse("/// A point.\n/// Immutable.\nclass P {\n  /// Builds one.\n  init() {}\n}")[..]
+        {
+            [Stmt::Class { doc, methods, .. }] => {
+                assert_eq!(doc.as_deref(), Some("A point.\nImmutable."));
+                match &methods[..] {
+                    [Stmt::Function { doc, .. }] => assert_eq!(doc.as_deref(), Some("Builds one.")),
+                    other => panic!("expected one method, got {:?}", other),
+                }
+            }
+            other => panic!("expected one class, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn declaration_without_doc_comment_has_none() {
+        match &parse("// plain\nfun f(){}\n/// hi\nvar x;\nfun g(){}")[..] {
+            [Stmt::Function { doc: None, .. }, Stmt::Var { .. }, Stmt::Function { doc: None, .. }] =>
+                {}
+            other => panic!("expected two undocumented functions, got {:?}", other),
+        }
+    }
+}
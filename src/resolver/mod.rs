@@ -0,0 +1,469 @@
+use std::collections::HashMap;
+
+use crate::{
+    ast::{Expr, ExprVisitor, Stmt, StmtVisitor},
+    error::NZErrors,
+    interpreter::Interpreter,
+    token::{Literal, Token},
+};
+
+#[derive(Clone, Copy, PartialEq)]
+enum FunctionType {
+    None,
+    Function,
+    Method,
+    Initializer,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum ClassType {
+    None,
+    Class,
+    Subclass,
+}
+
+/// Static pass run between parsing and interpreting. Records, for every
+/// variable, `this` and `super` expression, how many environments away its
+/// binding lives, and rejects programs that misuse scopes.
+pub struct Resolver<'a> {
+    interpreter: &'a mut Interpreter,
+    // false while a variable is declared but its initializer is not resolved yet
+    scopes: Vec<HashMap<String, bool>>,
+    current_function: FunctionType,
+    current_class: ClassType,
+}
+
+impl<'a> Resolver<'a> {
+    pub fn new(interpreter: &'a mut Interpreter) -> Self {
+        Self {
+            interpreter,
+            scopes: Vec::new(),
+            current_function: FunctionType::None,
+            current_class: ClassType::None,
+        }
+    }
+
+    pub fn resolve(&mut self, statements: &[Stmt]) -> Result<(), NZErrors> {
+        for stmt in statements {
+            self.resolve_stmt(stmt)?;
+        }
+        Ok(())
+    }
+
+    fn resolve_stmt(&mut self, stmt: &Stmt) -> Result<(), NZErrors> {
+        stmt.accept(self).map_err(|error| {
+            // an error about the statement itself underlines all of it
+            match (error.token(), stmt.span()) {
+                (Some(at), Some(span)) if Some(at.id) == own_token(stmt) => error.with_span(span),
+                _ => error,
+            }
+        })
+    }
+
+    fn resolve_expr(&mut self, expr: &Expr) -> Result<(), NZErrors> {
+        expr.accept(self)
+    }
+
+    fn resolve_function(
+        &mut self,
+        params: &[Token],
+        body: &[Stmt],
+        function_type: FunctionType,
+    ) -> Result<(), NZErrors> {
+        let enclosing_function = self.current_function;
+        self.current_function = function_type;
+
+        self.begin_scope();
+        let result = (|| {
+            for param in params {
+                self.declare(param)?;
+                self.define(param);
+            }
+            self.resolve(body)
+        })();
+        self.end_scope();
+
+        self.current_function = enclosing_function;
+        result
+    }
+
+    fn begin_scope(&mut self) {
+        self.scopes.push(HashMap::new());
+    }
+
+    fn end_scope(&mut self) {
+        self.scopes.pop();
+    }
+
+    fn declare(&mut self, name: &Token) -> Result<(), NZErrors> {
+        if let Some(scope) = self.scopes.last_mut() {
+            if scope.contains_key(&name.lexeme) {
+                return Err(NZErrors::ResolveError(
+                    name.clone(),
+                    "Already a variable with this name in this scope.".to_string(),
+                ));
+            }
+            scope.insert(name.lexeme.clone(), false);
+        }
+        Ok(())
+    }
+
+    fn define(&mut self, name: &Token) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name.lexeme.clone(), true);
+        }
+    }
+
+    fn resolve_local(&mut self, name: &Token) {
+        for (depth, scope) in self.scopes.iter().rev().enumerate() {
+            if scope.contains_key(&name.lexeme) {
+                self.interpreter.resolve(name, depth);
+                return;
+            }
+        }
+        // not found in any scope: assume it is global
+    }
+}
+
+/// Id of the token that errors about `stmt` itself are reported at: its
+/// keyword, or the name it declares.
+fn own_token(stmt: &Stmt) -> Option<usize> {
+    match stmt {
+        Stmt::Return { keyword, .. } | Stmt::Break { keyword } | Stmt::Continue { keyword } => {
+            Some(keyword.id)
+        }
+        Stmt::Var { name, .. } | Stmt::Function { name, .. } | Stmt::Class { name, .. } => {
+            Some(name.id)
+        }
+        _ => None,
+    }
+}
+
+impl ExprVisitor<Result<(), NZErrors>> for Resolver<'_> {
+    fn visit_binary_expr(
+        &mut self,
+        left: &Expr,
+        _op: &Token,
+        right: &Expr,
+    ) -> Result<(), NZErrors> {
+        self.resolve_expr(left)?;
+        self.resolve_expr(right)
+    }
+
+    fn visit_grouping_expr(&mut self, expression: &Expr) -> Result<(), NZErrors> {
+        self.resolve_expr(expression)
+    }
+
+    fn visit_literal_expr(&mut self, _literal: &Literal) -> Result<(), NZErrors> {
+        Ok(())
+    }
+
+    fn visit_unary_expr(&mut self, _op: &Token, expr: &Expr) -> Result<(), NZErrors> {
+        self.resolve_expr(expr)
+    }
+
+    fn visit_variable_expr(&mut self, name: &Token) -> Result<(), NZErrors> {
+        if let Some(scope) = self.scopes.last()
+            && scope.get(&name.lexeme) == Some(&false)
+        {
+            return Err(NZErrors::ResolveError(
+                name.clone(),
+                "Can't read local variable in its own initializer.".to_string(),
+            ));
+        }
+        self.resolve_local(name);
+        Ok(())
+    }
+
+    fn visit_assign_expr(&mut self, name: &Token, value: &Expr) -> Result<(), NZErrors> {
+        self.resolve_expr(value)?;
+        self.resolve_local(name);
+        Ok(())
+    }
+
+    fn visit_logical_expr(
+        &mut self,
+        left: &Expr,
+        _op: &Token,
+        right: &Expr,
+    ) -> Result<(), NZErrors> {
+        self.resolve_expr(left)?;
+        self.resolve_expr(right)
+    }
+
+    fn visit_call_expr(
+        &mut self,
+        callee: &Expr,
+        _paren: &Token,
+        arguments: &[Expr],
+    ) -> Result<(), NZErrors> {
+        self.resolve_expr(callee)?;
+        for arg in arguments {
+            self.resolve_expr(arg)?;
+        }
+        Ok(())
+    }
+
+    fn visit_get_expr(&mut self, object: &Expr, _name: &Token) -> Result<(), NZErrors> {
+        self.resolve_expr(object)
+    }
+
+    fn visit_set_expr(
+        &mut self,
+        object: &Expr,
+        _name: &Token,
+        value: &Expr,
+    ) -> Result<(), NZErrors> {
+        self.resolve_expr(value)?;
+        self.resolve_expr(object)
+    }
+
+    fn visit_compound_set_expr(
+        &mut self,
+        object: &Expr,
+        _name: &Token,
+        _op: &Token,
+        value: &Expr,
+    ) -> Result<(), NZErrors> {
+        self.resolve_expr(value)?;
+        self.resolve_expr(object)
+    }
+
+    fn visit_this_expr(&mut self, name: &Token) -> Result<(), NZErrors> {
+        if self.current_class == ClassType::None {
+            return Err(NZErrors::ResolveError(
+                name.clone(),
+                "Can't use 'this' outside of a class.".to_string(),
+            ));
+        }
+        self.resolve_local(name);
+        Ok(())
+    }
+
+    fn visit_super_expr(&mut self, name: &Token, _method: &Token) -> Result<(), NZErrors> {
+        match self.current_class {
+            ClassType::None => Err(NZErrors::ResolveError(
+                name.clone(),
+                "Can't use 'super' outside of a class.".to_string(),
+            )),
+            ClassType::Class => Err(NZErrors::ResolveError(
+                name.clone(),
+                "Can't use 'super' in a class with no superclass.".to_string(),
+            )),
+            ClassType::Subclass => {
+                self.resolve_local(name);
+                Ok(())
+            }
+        }
+    }
+
+    fn visit_list_expr(&mut self, _bracket: &Token, elements: &[Expr]) -> Result<(), NZErrors> {
+        for element in elements {
+            self.resolve_expr(element)?;
+        }
+        Ok(())
+    }
+
+    fn visit_map_expr(&mut self, _brace: &Token, entries: &[(Expr, Expr)]) -> Result<(), NZErrors> {
+        for (key, value) in entries {
+            self.resolve_expr(key)?;
+            self.resolve_expr(value)?;
+        }
+        Ok(())
+    }
+
+    fn visit_index_expr(
+        &mut self,
+        object: &Expr,
+        _bracket: &Token,
+        index: &Expr,
+    ) -> Result<(), NZErrors> {
+        self.resolve_expr(object)?;
+        self.resolve_expr(index)
+    }
+
+    fn visit_index_set_expr(
+        &mut self,
+        object: &Expr,
+        _bracket: &Token,
+        index: &Expr,
+        value: &Expr,
+    ) -> Result<(), NZErrors> {
+        self.resolve_expr(value)?;
+        self.resolve_expr(object)?;
+        self.resolve_expr(index)
+    }
+
+    fn visit_lambda_expr(
+        &mut self,
+        _keyword: &Token,
+        params: &[Token],
+        body: &[Stmt],
+    ) -> Result<(), NZErrors> {
+        self.resolve_function(params, body, FunctionType::Function)
+    }
+
+    fn visit_interpolate_expr(&mut self, expr: &Expr) -> Result<(), NZErrors> {
+        self.resolve_expr(expr)
+    }
+}
+
+impl StmtVisitor<Result<(), NZErrors>> for Resolver<'_> {
+    fn visit_expression_stmt(&mut self, expression: &Expr) -> Result<(), NZErrors> {
+        self.resolve_expr(expression)
+    }
+
+    fn visit_print_stmt(&mut self, expression: &Expr) -> Result<(), NZErrors> {
+        self.resolve_expr(expression)
+    }
+
+    fn visit_var_stmt(&mut self, name: &Token, initializer: &Option<Expr>) -> Result<(), NZErrors> {
+        self.declare(name)?;
+        if let Some(initializer) = initializer {
+            self.resolve_expr(initializer)?;
+        }
+        self.define(name);
+        Ok(())
+    }
+
+    fn visit_block_stmt(&mut self, statements: &[Stmt]) -> Result<(), NZErrors> {
+        self.begin_scope();
+        let result = self.resolve(statements);
+        self.end_scope();
+        result
+    }
+
+    fn visit_if_stmt(
+        &mut self,
+        condition: &Expr,
+        then_branch: &Stmt,
+        else_branch: &Option<Box<Stmt>>,
+    ) -> Result<(), NZErrors> {
+        self.resolve_expr(condition)?;
+        self.resolve_stmt(then_branch)?;
+        if let Some(else_branch) = else_branch {
+            self.resolve_stmt(else_branch)?;
+        }
+        Ok(())
+    }
+
+    fn visit_while_stmt(
+        &mut self,
+        condition: &Expr,
+        body: &Stmt,
+        increment: &Option<Expr>,
+    ) -> Result<(), NZErrors> {
+        self.resolve_expr(condition)?;
+        self.resolve_stmt(body)?;
+        if let Some(increment) = increment {
+            self.resolve_expr(increment)?;
+        }
+        Ok(())
+    }
+
+    // the parser already rejects these outside of a loop
+    fn visit_break_stmt(&mut self, _keyword: &Token) -> Result<(), NZErrors> {
+        Ok(())
+    }
+
+    fn visit_continue_stmt(&mut self, _keyword: &Token) -> Result<(), NZErrors> {
+        Ok(())
+    }
+
+    fn visit_function_stmt(
+        &mut self,
+        name: &Token,
+        params: &[Token],
+        body: &[Stmt],
+    ) -> Result<(), NZErrors> {
+        // define eagerly so the function can refer to itself recursively
+        self.declare(name)?;
+        self.define(name);
+        self.resolve_function(params, body, FunctionType::Function)
+    }
+
+    fn visit_return_stmt(&mut self, keyword: &Token, value: &Option<Expr>) -> Result<(), NZErrors> {
+        if self.current_function == FunctionType::None {
+            return Err(NZErrors::ResolveError(
+                keyword.clone(),
+                "Can't return from top-level code.".to_string(),
+            ));
+        }
+        if let Some(value) = value {
+            if self.current_function == FunctionType::Initializer {
+                return Err(NZErrors::ResolveError(
+                    keyword.clone(),
+                    "Can't return a value from an initializer.".to_string(),
+                ));
+            }
+            self.resolve_expr(value)?;
+        }
+        Ok(())
+    }
+
+    fn visit_class_stmt(
+        &mut self,
+        name: &Token,
+        superclass: &Option<Expr>,
+        methods: &[Stmt],
+    ) -> Result<(), NZErrors> {
+        if let Some(Expr::Variable(super_name)) = superclass
+            && super_name.lexeme == name.lexeme
+        {
+            return Err(NZErrors::ResolveError(
+                super_name.clone(),
+                "A class can't inherit from itself.".to_string(),
+            ));
+        }
+
+        let enclosing_class = self.current_class;
+        self.current_class = ClassType::Class;
+
+        self.declare(name)?;
+        self.define(name);
+
+        if let Some(superclass) = superclass {
+            self.current_class = ClassType::Subclass;
+            self.resolve_expr(superclass)?;
+
+            // mirrors the environment the interpreter creates to hold "super"
+            self.begin_scope();
+            self.scopes
+                .last_mut()
+                .unwrap()
+                .insert("super".to_string(), true);
+        }
+
+        // mirrors the environment LoxFunction::bind creates to hold "this"
+        self.begin_scope();
+        self.scopes
+            .last_mut()
+            .unwrap()
+            .insert("this".to_string(), true);
+
+        let result = (|| {
+            for method in methods {
+                if let Stmt::Function {
+                    name, params, body, ..
+                } = method
+                {
+                    let function_type = if name.lexeme == "init" {
+                        FunctionType::Initializer
+                    } else {
+                        FunctionType::Method
+                    };
+                    self.resolve_function(params, body, function_type)?;
+                }
+            }
+            Ok(())
+        })();
+
+        self.end_scope();
+        if superclass.is_some() {
+            self.end_scope();
+        }
+
+        self.current_class = enclosing_class;
+        result
+    }
+}
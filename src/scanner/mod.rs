@@ -2,15 +2,27 @@ use std::collections::HashMap;
 
 use crate::{
     error::NZErrors,
-    token::{token_types::TokenType, Literal, Token},
+    token::{token_types::TokenType, Literal, Span, Token},
 };
 
+/// Turns source text into tokens in a single pass. `start` and `current` are
+/// byte offsets into `source`, always on a character boundary; columns are
+/// counted in characters.
 pub struct Scanner<'a> {
     source: String,
     tokens: Vec<Token>,
     start: usize,
     current: usize,
     line: usize,
+    // offset where the current line begins, and characters consumed since
+    line_start: usize,
+    column: usize,
+    // line and column at `start`, since strings may span several lines
+    start_line: usize,
+    start_column: usize,
+    // one entry per `${` still open, counting the `{` nested inside it, so
+    // the `}` that closes the interpolation resumes the string
+    interpolations: Vec<usize>,
     keywords: HashMap<&'a str, TokenType>,
 }
 
@@ -22,9 +34,16 @@ impl<'a> Scanner<'a> {
             start: 0,
             current: 0,
             line: 1,
+            line_start: 0,
+            column: 0,
+            start_line: 1,
+            start_column: 1,
+            interpolations: vec![],
             keywords: HashMap::from([
                 ("and", TokenType::AND),
+                ("break", TokenType::BREAK),
                 ("class", TokenType::CLASS),
+                ("continue", TokenType::CONTINUE),
                 ("else", TokenType::ELSE),
                 ("false", TokenType::FALSE),
                 ("for", TokenType::FOR),
@@ -44,152 +63,209 @@ impl<'a> Scanner<'a> {
     }
 
     pub fn scan_tokens(&mut self) -> Result<Vec<Token>, NZErrors> {
-        while !self.is_end() {
+        loop {
             self.start = self.current;
-            self.scan_token()?;
+            self.start_line = self.line;
+            self.start_column = self.column + 1;
+            let Some(c) = self.next() else {
+                break;
+            };
+            self.scan_token(c)?;
+        }
+        if !self.interpolations.is_empty() {
+            return Err(self.error_at(self.current, "Unterminated string interpolation."));
         }
 
-        self.tokens.push(Token::new(
-            TokenType::EOF,
-            "".to_string(),
-            Literal::Nil,
-            self.line.try_into().unwrap(),
-        ));
+        self.start = self.current;
+        self.start_line = self.line;
+        self.start_column = self.column + 1;
+        self.add_token(TokenType::EOF, Literal::Nil);
         Ok(self.tokens.clone())
     }
 
-    fn scan_token(&mut self) -> Result<(), NZErrors> {
-        let c = self.next();
-
+    fn scan_token(&mut self, c: char) -> Result<(), NZErrors> {
         match c {
-            Some(c) => match c {
-                '(' => self.add_token(TokenType::LEFTPAREN, Literal::Nil),
-                ')' => self.add_token(TokenType::RIGHTPAREN, Literal::Nil),
-                '{' => self.add_token(TokenType::LEFTBRACE, Literal::Nil),
-                '}' => self.add_token(TokenType::RIGHTBRACE, Literal::Nil),
-                ',' => self.add_token(TokenType::COMMA, Literal::Nil),
-                '.' => self.add_token(TokenType::DOT, Literal::Nil),
-                '-' => self.add_token(TokenType::MINUS, Literal::Nil),
-                '+' => self.add_token(TokenType::PLUS, Literal::Nil),
-                ';' => self.add_token(TokenType::SEMICOLON, Literal::Nil),
-                '*' => self.add_token(TokenType::STAR, Literal::Nil),
-                '/' => {
-                    if self.match_char('/') {
-                        while self.peek() != Some('\n') && !self.is_end() {
-                            self.next();
-                        }
-                    } else {
-                        self.add_token(TokenType::SLASH, Literal::Nil)
-                    }
+            '(' => self.add_token(TokenType::LEFTPAREN, Literal::Nil),
+            ')' => self.add_token(TokenType::RIGHTPAREN, Literal::Nil),
+            '{' => {
+                if let Some(depth) = self.interpolations.last_mut() {
+                    *depth += 1;
                 }
-                '!' => {
-                    let p = self.match_char('=');
-                    self.add_token(
-                        if let true = p {
-                            TokenType::BANGEQUAL
-                        } else {
-                            TokenType::BANG
-                        },
-                        Literal::Nil,
-                    )
+                self.add_token(TokenType::LEFTBRACE, Literal::Nil)
+            }
+            '}' => match self.interpolations.last_mut() {
+                Some(0) => {
+                    self.interpolations.pop();
+                    self.string(TokenType::INTERPOLATIONEND)?;
                 }
-
-                '<' => {
-                    let p = self.match_char('=');
-                    self.add_token(
-                        if let true = p {
-                            TokenType::LESSEQUAL
-                        } else {
-                            TokenType::LESS
-                        },
-                        Literal::Nil,
-                    )
+                Some(depth) => {
+                    *depth -= 1;
+                    self.add_token(TokenType::RIGHTBRACE, Literal::Nil)
                 }
-
-                '>' => {
-                    let p = self.match_char('=');
-                    self.add_token(
-                        if let true = p {
-                            TokenType::GREATEREQUAL
-                        } else {
-                            TokenType::GREATER
-                        },
-                        Literal::Nil,
-                    )
+                None => self.add_token(TokenType::RIGHTBRACE, Literal::Nil),
+            },
+            '[' => self.add_token(TokenType::LEFTBRACKET, Literal::Nil),
+            ']' => self.add_token(TokenType::RIGHTBRACKET, Literal::Nil),
+            ',' => self.add_token(TokenType::COMMA, Literal::Nil),
+            ':' => self.add_token(TokenType::COLON, Literal::Nil),
+            '.' => self.add_token(TokenType::DOT, Literal::Nil),
+            '-' => {
+                let p = self.match_char('=');
+                self.add_token(
+                    if p {
+                        TokenType::MINUSEQUAL
+                    } else {
+                        TokenType::MINUS
+                    },
+                    Literal::Nil,
+                )
+            }
+            '%' => self.add_token(TokenType::PERCENT, Literal::Nil),
+            '+' => {
+                let p = self.match_char('=');
+                self.add_token(
+                    if p {
+                        TokenType::PLUSEQUAL
+                    } else {
+                        TokenType::PLUS
+                    },
+                    Literal::Nil,
+                )
+            }
+            ';' => self.add_token(TokenType::SEMICOLON, Literal::Nil),
+            '*' => {
+                let token_type = if self.match_char('*') {
+                    TokenType::STARSTAR
+                } else if self.match_char('=') {
+                    TokenType::STAREQUAL
+                } else {
+                    TokenType::STAR
+                };
+                self.add_token(token_type, Literal::Nil)
+            }
+            '~' => {
+                if self.match_char('/') {
+                    self.add_token(TokenType::TILDESLASH, Literal::Nil)
+                } else {
+                    return Err(self.error_at(self.start, "Expect '/' after '~'."));
                 }
-
-                '=' => {
-                    let p = self.match_char('=');
-                    self.add_token(
-                        if let true = p {
-                            TokenType::EQUALEQUAL
-                        } else {
-                            TokenType::EQUAL
-                        },
-                        Literal::Nil,
-                    )
+            }
+            '/' => {
+                // `////...` is an ordinary comment, as are rulers of slashes
+                if self.peek() == Some('/')
+                    && self.peek_next() == Some('/')
+                    && !self.source[self.current + 2..].starts_with('/')
+                {
+                    self.doc_comment();
+                } else if self.match_char('*') {
+                    self.block_comment()?;
+                } else if self.match_char('/') {
+                    while self.peek() != Some('\n') && !self.is_end() {
+                        self.next();
+                    }
+                } else if self.match_char('=') {
+                    self.add_token(TokenType::SLASHEQUAL, Literal::Nil)
+                } else {
+                    self.add_token(TokenType::SLASH, Literal::Nil)
                 }
+            }
+            '!' => {
+                let p = self.match_char('=');
+                self.add_token(
+                    if p {
+                        TokenType::BANGEQUAL
+                    } else {
+                        TokenType::BANG
+                    },
+                    Literal::Nil,
+                )
+            }
 
-                '\n' => self.line += 1,
-                '\t' | '\r' | ' ' => {}
-                '"' => self.string(),
+            '<' => {
+                let p = self.match_char('=');
+                self.add_token(
+                    if p {
+                        TokenType::LESSEQUAL
+                    } else {
+                        TokenType::LESS
+                    },
+                    Literal::Nil,
+                )
+            }
 
-                _ => {
-                    if Scanner::is_digit(c) {
-                        self.number();
-                    } else if Scanner::is_alpha(c) {
-                        self.identifier()
+            '>' => {
+                let p = self.match_char('=');
+                self.add_token(
+                    if p {
+                        TokenType::GREATEREQUAL
                     } else {
-                        return Err(NZErrors::ParseError(
-                            self.tokens[self.tokens.len() - 1].clone(),
-                            "Unexpected character.".to_string(),
-                        ));
-                    }
+                        TokenType::GREATER
+                    },
+                    Literal::Nil,
+                )
+            }
+
+            '=' => {
+                let token_type = if self.match_char('=') {
+                    TokenType::EQUALEQUAL
+                } else if self.match_char('>') {
+                    TokenType::ARROW
+                } else {
+                    TokenType::EQUAL
+                };
+                self.add_token(token_type, Literal::Nil)
+            }
+
+            '\n' => self.new_line(),
+            '\t' | '\r' | ' ' => {}
+            '"' => self.string(TokenType::STRING)?,
+
+            _ => {
+                if Scanner::is_digit(c) {
+                    self.number();
+                } else if Scanner::is_alpha(c) {
+                    self.identifier()
+                } else {
+                    return Err(self.error_at(self.start, "Unexpected character."));
                 }
-            },
-            None => {
-                return Err(NZErrors::ParseError(
-                    self.tokens[self.tokens.len() - 1].clone(),
-                    "Unexpected end of input.".to_string(),
-                ))
             }
         }
         Ok(())
     }
 
+    // identifiers may use any Unicode letter; numbers stay ASCII
     fn is_alpha(c: char) -> bool {
-        return (c >= 'a' && c <= 'z') || (c >= 'A' && c <= 'Z') || c == '_';
+        c.is_alphabetic() || c == '_'
     }
 
     fn is_digit(c: char) -> bool {
-        return c >= '0' && c <= '9';
+        c.is_ascii_digit()
     }
 
     fn is_alphanumeric(c: char) -> bool {
-        return Scanner::is_digit(c) || Scanner::is_alpha(c);
+        c.is_alphanumeric() || c == '_'
     }
 
     fn next(&mut self) -> Option<char> {
-        self.current += 1;
-        self.source.chars().nth(self.current - 1)
+        let c = self.peek()?;
+        self.current += c.len_utf8();
+        self.column += 1;
+        Some(c)
     }
 
     fn peek(&self) -> Option<char> {
-        self.source.chars().nth(self.current)
+        self.source[self.current..].chars().next()
     }
 
     fn peek_next(&self) -> Option<char> {
-        self.source.chars().nth(self.current + 1)
+        self.source[self.current..].chars().nth(1)
     }
 
     fn match_char(&mut self, expected: char) -> bool {
-        if self.is_end() {
+        if self.peek() != Some(expected) {
             return false;
         }
-        if self.source.chars().nth(self.current).unwrap() != expected {
-            return false;
-        }
-        self.current += 1;
+        self.next();
         true
     }
 
@@ -197,52 +273,166 @@ impl<'a> Scanner<'a> {
         self.current >= self.source.len()
     }
 
+    fn new_line(&mut self) {
+        self.line += 1;
+        self.line_start = self.current;
+        self.column = 0;
+    }
+
     fn add_token(&mut self, token_type: TokenType, literal: Literal) {
         self.tokens.push(Token::new(
             token_type,
             self.source[self.start..self.current].to_string(),
             literal,
-            self.line.try_into().unwrap(),
+            self.start_line.try_into().unwrap(),
+            self.start_column.try_into().unwrap(),
+            Span::new(self.start, self.current),
         ));
     }
 
-    fn string(&mut self) {
-        while self.peek() != Some('"') && !self.is_end() {
-            if self.peek() == Some('\n') {
-                self.line += 1;
+    /// Scans a string literal, or the part of one up to a `${` or from the
+    /// `}` that ends an interpolation. A part followed by `${` becomes an
+    /// INTERPOLATION token; the last part becomes `last`, which is STRING for
+    /// a plain literal and INTERPOLATIONEND after a `}`.
+    fn string(&mut self, last: TokenType) -> Result<(), NZErrors> {
+        let mut value = String::new();
+        loop {
+            let Some(c) = self.next() else {
+                return Err(self.unterminated(TokenType::STRING, "Unterminated string."));
+            };
+            match c {
+                '"' => break,
+                '\\' => value.push(self.escape()?),
+                '$' if self.match_char('{') => {
+                    self.interpolations.push(0);
+                    self.add_token(TokenType::INTERPOLATION, Literal::String(value));
+                    return Ok(());
+                }
+                '\n' => {
+                    self.new_line();
+                    value.push('\n');
+                }
+                c => value.push(c),
             }
+        }
+        self.add_token(last, Literal::String(value));
+        Ok(())
+    }
+
+    /// Skips a `/* ... */` comment whose opening has been consumed. Block
+    /// comments nest, so a region containing one can still be commented out.
+    fn block_comment(&mut self) -> Result<(), NZErrors> {
+        let mut depth = 1;
+        while depth > 0 {
+            match self.next() {
+                None => {
+                    return Err(self.unterminated(TokenType::SLASH, "Unterminated block comment."))
+                }
+                Some('\n') => self.new_line(),
+                Some('/') if self.match_char('*') => depth += 1,
+                Some('*') if self.match_char('/') => depth -= 1,
+                Some(_) => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// A `///` comment becomes a DOC token holding its text, which the parser
+    /// attaches to the declaration that follows.
+    fn doc_comment(&mut self) {
+        while self.peek().is_some_and(|c| c != '\n') {
             self.next();
         }
-        if self.is_end() {
-            NZErrors::ParseError(
-                self.tokens[self.tokens.len() - 1].clone(),
-                "Unterminated string.".to_string(),
-            )
-            .report_error();
-            return;
+        let text = self.source[self.start + 3..self.current].trim_end_matches('\r');
+        let text = text.strip_prefix(' ').unwrap_or(text).to_string();
+        self.add_token(TokenType::DOC, Literal::String(text));
+    }
+
+    /// An error for a construct that runs to the end of the source, reported
+    /// at the place where it starts.
+    fn unterminated(&self, token_type: TokenType, message: &str) -> NZErrors {
+        NZErrors::ParseError(
+            Token::new(
+                token_type,
+                self.source[self.start..self.current].to_string(),
+                Literal::Nil,
+                self.start_line.try_into().unwrap(),
+                self.start_column.try_into().unwrap(),
+                Span::new(self.start, self.current),
+            ),
+            message.to_string(),
+        )
+    }
+
+    fn escape(&mut self) -> Result<char, NZErrors> {
+        let start = self.current - 1;
+        let c = if self.is_end() { None } else { self.next() };
+        match c {
+            Some('n') => Ok('\n'),
+            Some('t') => Ok('\t'),
+            Some('r') => Ok('\r'),
+            Some('0') => Ok('\0'),
+            Some('"') => Ok('"'),
+            Some('\\') => Ok('\\'),
+            Some('$') => Ok('$'),
+            Some('u') => {
+                if !self.match_char('{') {
+                    return Err(self.error_at(start, "Expect '{' after '\\u'."));
+                }
+                let digits = self.current;
+                while self.peek().is_some_and(|c| c.is_ascii_hexdigit()) {
+                    self.next();
+                }
+                let count = self.current - digits;
+                if count == 0 || count > 6 || !self.match_char('}') {
+                    return Err(self.error_at(start, "Invalid unicode escape."));
+                }
+                u32::from_str_radix(&self.source[digits..digits + count], 16)
+                    .ok()
+                    .and_then(char::from_u32)
+                    .ok_or_else(|| self.error_at(start, "Invalid unicode code point."))
+            }
+            _ => Err(self.error_at(start, "Invalid escape sequence.")),
         }
-        self.next();
-        let value = self.source[self.start + 1..self.current - 1].to_string();
-        println!("Value from string: {}", value);
-        self.add_token(TokenType::STRING, Literal::String(value));
+    }
+
+    /// An error covering the source from `start` to the current character,
+    /// which must all be on the current line.
+    fn error_at(&self, start: usize, message: &str) -> NZErrors {
+        NZErrors::ParseError(
+            Token::new(
+                TokenType::STRING,
+                self.source[start..self.current].to_string(),
+                Literal::Nil,
+                self.line.try_into().unwrap(),
+                (self.source[self.line_start..start].chars().count() + 1)
+                    .try_into()
+                    .unwrap(),
+                Span::new(start, self.current),
+            ),
+            message.to_string(),
+        )
     }
 
     fn number(&mut self) {
-        while Scanner::is_digit(self.peek().unwrap()) {
+        while self.peek().is_some_and(Scanner::is_digit) {
             self.next();
         }
-        if self.peek() == Some('.') && Scanner::is_digit(self.peek_next().unwrap()) {
+        if self.peek() == Some('.') && self.peek_next().is_some_and(Scanner::is_digit) {
             self.next();
-            while Scanner::is_digit(self.peek().unwrap()) {
+            while self.peek().is_some_and(Scanner::is_digit) {
                 self.next();
             }
         }
-        let value = self.source[self.start..self.current].to_string();
-        self.add_token(TokenType::NUMBER, Literal::Number(value.parse().unwrap()));
+        // only ASCII digits with at most one '.' between them reach here
+        let value = self.source[self.start..self.current]
+            .parse()
+            .unwrap_or(f64::NAN);
+        self.add_token(TokenType::NUMBER, Literal::Number(value));
     }
 
     fn identifier(&mut self) {
-        while Scanner::is_alphanumeric(self.peek().unwrap()) {
+        while self.peek().is_some_and(Scanner::is_alphanumeric) {
             self.next();
         }
 
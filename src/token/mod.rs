@@ -1,7 +1,10 @@
 use core::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 pub mod token_types;
 
+static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum Literal {
     Number(f64),
@@ -21,12 +24,39 @@ impl fmt::Display for Literal {
     }
 }
 
+/// Byte range `start..end` into the scanned source.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize) -> Span {
+        Span { start, end }
+    }
+
+    /// Smallest span covering both `self` and `other`.
+    pub fn to(self, other: Span) -> Span {
+        Span {
+            start: self.start.min(other.start),
+            end: self.end.max(other.end),
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct Token {
     pub token_type: token_types::TokenType,
     pub lexeme: String,
     pub literal: Literal,
     pub line: u32,
+    // 1-based, counted in characters from the start of `line`
+    pub column: u32,
+    pub span: Span,
+    // unique per scanned token, survives cloning; the resolver keys scope
+    // distances by it
+    pub id: usize,
 }
 
 impl Token {
@@ -35,21 +65,31 @@ impl Token {
         lexeme: String,
         literal: Literal,
         line: u32,
+        column: u32,
+        span: Span,
     ) -> Token {
         Token {
             token_type,
             lexeme,
             literal,
             line,
+            column,
+            span,
+            id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
         }
     }
+}
 
-    pub fn default() -> Token {
+impl Default for Token {
+    fn default() -> Token {
         Token {
             token_type: token_types::TokenType::EOF,
             lexeme: String::new(),
             literal: Literal::Nil,
             line: 0,
+            column: 0,
+            span: Span::default(),
+            id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
         }
     }
 }
@@ -1,3 +1,4 @@
+#[allow(clippy::upper_case_acronyms)]
 #[derive(Debug, Clone, PartialEq)]
 pub enum TokenType {
     // Single-character tokens.
@@ -5,15 +6,20 @@ pub enum TokenType {
     RIGHTPAREN,
     LEFTBRACE,
     RIGHTBRACE,
+    LEFTBRACKET,
+    RIGHTBRACKET,
     COMMA,
+    COLON,
     DOT,
     MINUS,
+    PERCENT,
     PLUS,
     SEMICOLON,
     SLASH,
     STAR,
 
     // One or two character tokens.
+    ARROW,
     BANG,
     BANGEQUAL,
     EQUAL,
@@ -22,15 +28,27 @@ pub enum TokenType {
     GREATEREQUAL,
     LESS,
     LESSEQUAL,
+    MINUSEQUAL,
+    PLUSEQUAL,
+    SLASHEQUAL,
+    STAREQUAL,
+    STARSTAR,
+    TILDESLASH,
 
     // Literals.
     IDENTIFIER,
     STRING,
+    // the part of a string literal before a `${`
+    INTERPOLATION,
+    // the part of an interpolated string after its last `}`
+    INTERPOLATIONEND,
     NUMBER,
 
     // Keywords.
     AND,
+    BREAK,
     CLASS,
+    CONTINUE,
     ELSE,
     FALSE,
     FUN,
@@ -46,5 +64,7 @@ pub enum TokenType {
     VAR,
     WHILE,
 
+    // `///` comment text, consumed by the parser before parsing
+    DOC,
     EOF,
 }
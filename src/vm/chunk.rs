@@ -0,0 +1,114 @@
+use std::{collections::HashMap, rc::Rc};
+
+use crate::{error::NZErrors, object::Object, token::Token};
+
+use super::function::Function;
+
+/// One VM instruction. Operands are indices into the chunk's constant or
+/// function pool, stack slots, or jump distances counted in instructions.
+/// Instructions that name a global, a property or a method take the name
+/// from the token recorded with them.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OpCode {
+    Constant(u16),
+    Nil,
+    True,
+    False,
+    Pop,
+    // pushes a copy of the top of the stack
+    Dup,
+    GetLocal(u8),
+    SetLocal(u8),
+    GetUpvalue(u8),
+    SetUpvalue(u8),
+    GetGlobal,
+    DefineGlobal,
+    SetGlobal,
+    GetProperty,
+    SetProperty,
+    // pops the superclass and `this`, pushes the bound superclass method
+    GetSuper,
+    GetIndex,
+    SetIndex,
+    // the operator is the token recorded with the instruction
+    Unary,
+    Binary,
+    Print,
+    // replaces the value on top with its printed form, for `${...}`
+    Stringify,
+    Jump(u16),
+    JumpIfFalse(u16),
+    Loop(u16),
+    Call(u8),
+    Closure(u16),
+    CloseUpvalue,
+    Return,
+    // method count, and whether a superclass sits below the methods
+    Class(u8, bool),
+    List(u16),
+    Map(u16),
+    // add that many elements, or key/value pairs, to the list or map below
+    // them; long literals are built in batches
+    ExtendList(u16),
+    ExtendMap(u16),
+}
+
+/// A compiled function body: instructions, the token each instruction came
+/// from (for runtime errors), literal constants and nested functions.
+#[derive(Debug, Default)]
+pub struct Chunk {
+    pub code: Vec<OpCode>,
+    pub tokens: Vec<Token>,
+    pub constants: Vec<Object>,
+    pub functions: Vec<Rc<Function>>,
+    // where each number and string constant already is, so repeating a
+    // literal doesn't use up the pool
+    slots: HashMap<Constant, u16>,
+}
+
+#[derive(Debug, PartialEq, Eq, Hash)]
+enum Constant {
+    // by bit pattern, which keeps 0 and -0 apart
+    Number(u64),
+    String(String),
+}
+
+impl Chunk {
+    pub fn write(&mut self, op: OpCode, token: Token) -> usize {
+        self.code.push(op);
+        self.tokens.push(token);
+        self.code.len() - 1
+    }
+
+    pub fn add_constant(&mut self, value: Object, token: &Token) -> Result<u16, NZErrors> {
+        let key = match &value {
+            Object::Number(n) => Some(Constant::Number(n.to_bits())),
+            Object::String(s) => Some(Constant::String(s.clone())),
+            _ => None,
+        };
+        if let Some(slot) = key.as_ref().and_then(|key| self.slots.get(key)) {
+            return Ok(*slot);
+        }
+        self.constants.push(value);
+        let slot = u16::try_from(self.constants.len() - 1).map_err(|_| {
+            NZErrors::CompileError(
+                token.clone(),
+                "Too many constants in one chunk.".to_string(),
+            )
+        })?;
+        if let Some(key) = key {
+            self.slots.insert(key, slot);
+        }
+        Ok(slot)
+    }
+
+    pub fn add_function(&mut self, function: Function, token: &Token) -> Result<u16, NZErrors> {
+        self.functions.push(Rc::new(function));
+        u16::try_from(self.functions.len() - 1).map_err(|_| {
+            NZErrors::CompileError(
+                token.clone(),
+                "Too many functions in one chunk.".to_string(),
+            )
+        })
+    }
+}
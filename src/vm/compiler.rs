@@ -0,0 +1,774 @@
+use std::rc::Rc;
+
+use crate::{
+    ast::{Expr, ExprVisitor, Stmt, StmtVisitor},
+    error::NZErrors,
+    object::Object,
+    token::{token_types::TokenType, Literal, Token},
+};
+
+use super::{
+    chunk::{Chunk, OpCode},
+    function::{Function, UpvalueSource},
+};
+
+#[derive(Clone, Copy, PartialEq)]
+enum FunctionType {
+    Script,
+    Function,
+    Method,
+    Initializer,
+}
+
+struct Local {
+    name: String,
+    depth: usize,
+    // closed over by a nested function, so it must outlive its stack slot
+    captured: bool,
+}
+
+/// Compiler state for one function body; nested declarations push another.
+struct FunctionState {
+    function: Function,
+    function_type: FunctionType,
+    locals: Vec<Local>,
+    scope_depth: usize,
+    loops: Vec<Loop>,
+}
+
+/// A loop being compiled. `break` and `continue` jump forward to targets
+/// that are only known once the body is done.
+struct Loop {
+    // locals deeper than this belong to the body and are dropped on a jump
+    scope_depth: usize,
+    breaks: Vec<usize>,
+    continues: Vec<usize>,
+}
+
+/// Turns a resolved program into bytecode for the VM. Runs after the
+/// `Resolver`, so the program is known to be well formed; the compiler only
+/// decides where every variable lives: a stack slot, an upvalue or a global.
+pub struct Compiler {
+    states: Vec<FunctionState>,
+}
+
+impl Compiler {
+    /// Compiles a whole program into the function the VM runs as its script.
+    pub fn compile(statements: &[Stmt]) -> Result<Rc<Function>, NZErrors> {
+        let mut compiler = Compiler::new();
+        for stmt in statements {
+            stmt.accept(&mut compiler)?;
+        }
+        compiler.emit_return();
+        Ok(Rc::new(compiler.finish()))
+    }
+
+    /// Compiles a single expression into a script that returns its value.
+    pub fn compile_expression(expression: &Expr) -> Result<Rc<Function>, NZErrors> {
+        let mut compiler = Compiler::new();
+        expression.accept(&mut compiler)?;
+        compiler.emit(OpCode::Return, &Token::default());
+        Ok(Rc::new(compiler.finish()))
+    }
+
+    fn new() -> Self {
+        let mut compiler = Self { states: Vec::new() };
+        compiler.begin_function("script", 0, FunctionType::Script);
+        compiler
+    }
+
+    fn finish(&mut self) -> Function {
+        self.states.pop().expect("no function to finish").function
+    }
+
+    fn state(&mut self) -> &mut FunctionState {
+        self.states.last_mut().expect("compiler has no function")
+    }
+
+    fn chunk(&mut self) -> &mut Chunk {
+        &mut self.state().function.chunk
+    }
+
+    fn emit(&mut self, op: OpCode, token: &Token) -> usize {
+        self.chunk().write(op, token.clone())
+    }
+
+    fn emit_constant(&mut self, value: Object, token: &Token) -> Result<(), NZErrors> {
+        let index = self.chunk().add_constant(value, token)?;
+        self.emit(OpCode::Constant(index), token);
+        Ok(())
+    }
+
+    /// Emits the implicit `return` at the end of a function body.
+    fn emit_return(&mut self) {
+        let token = Token::default();
+        if self.state().function_type == FunctionType::Initializer {
+            self.emit(OpCode::GetLocal(0), &token);
+        } else {
+            self.emit(OpCode::Nil, &token);
+        }
+        self.emit(OpCode::Return, &token);
+    }
+
+    /// Emits a forward jump whose distance is filled in by `patch_jump`.
+    fn emit_jump(&mut self, jump: fn(u16) -> OpCode) -> usize {
+        self.emit(jump(0), &Token::default())
+    }
+
+    fn patch_jump(&mut self, at: usize) -> Result<(), NZErrors> {
+        let distance = jump_distance(self.chunk().code.len() - at - 1)?;
+        let chunk = self.chunk();
+        chunk.code[at] = match chunk.code[at] {
+            OpCode::Jump(_) => OpCode::Jump(distance),
+            OpCode::JumpIfFalse(_) => OpCode::JumpIfFalse(distance),
+            op => unreachable!("{:?} is not a jump", op),
+        };
+        Ok(())
+    }
+
+    fn emit_loop(&mut self, start: usize) -> Result<(), NZErrors> {
+        let distance = jump_distance(self.chunk().code.len() + 1 - start)?;
+        self.emit(OpCode::Loop(distance), &Token::default());
+        Ok(())
+    }
+
+    fn begin_scope(&mut self) {
+        self.state().scope_depth += 1;
+    }
+
+    fn end_scope(&mut self) {
+        self.state().scope_depth -= 1;
+        let depth = self.state().scope_depth;
+        let kept = self.discard_locals(depth);
+        self.state().locals.truncate(kept);
+    }
+
+    /// Emits the pops for the locals declared deeper than `depth`, without
+    /// forgetting them, and returns how many locals are left.
+    fn discard_locals(&mut self, depth: usize) -> usize {
+        let ops: Vec<OpCode> = self
+            .state()
+            .locals
+            .iter()
+            .rev()
+            .take_while(|local| local.depth > depth)
+            .map(|local| {
+                if local.captured {
+                    OpCode::CloseUpvalue
+                } else {
+                    OpCode::Pop
+                }
+            })
+            .collect();
+        for op in &ops {
+            self.emit(*op, &Token::default());
+        }
+        self.state().locals.len() - ops.len()
+    }
+
+    fn patch_jumps_to_here(&mut self, jumps: Vec<usize>) -> Result<(), NZErrors> {
+        for jump in jumps {
+            self.patch_jump(jump)?;
+        }
+        Ok(())
+    }
+
+    fn add_local(&mut self, name: &str, token: &Token) -> Result<(), NZErrors> {
+        let state = self.state();
+        if state.locals.len() > u8::MAX as usize {
+            return Err(NZErrors::CompileError(
+                token.clone(),
+                "Too many local variables in function.".to_string(),
+            ));
+        }
+        let depth = state.scope_depth;
+        state.locals.push(Local {
+            name: name.to_string(),
+            depth,
+            captured: false,
+        });
+        Ok(())
+    }
+
+    /// Binds the value on top of the stack to `name`: as a new local inside
+    /// a scope, as a global at the top level.
+    fn define_variable(&mut self, name: &Token) -> Result<(), NZErrors> {
+        if self.state().scope_depth > 0 {
+            self.add_local(&name.lexeme, name)
+        } else {
+            self.emit(OpCode::DefineGlobal, name);
+            Ok(())
+        }
+    }
+
+    fn resolve_local(&self, state: usize, name: &str) -> Option<u8> {
+        self.states[state]
+            .locals
+            .iter()
+            .rposition(|local| local.name == name)
+            .map(|slot| slot as u8)
+    }
+
+    fn resolve_upvalue(
+        &mut self,
+        state: usize,
+        name: &str,
+        token: &Token,
+    ) -> Result<Option<u8>, NZErrors> {
+        if state == 0 {
+            return Ok(None);
+        }
+        if let Some(slot) = self.resolve_local(state - 1, name) {
+            self.states[state - 1].locals[slot as usize].captured = true;
+            let source = UpvalueSource {
+                is_local: true,
+                index: slot,
+            };
+            return self.add_upvalue(state, source, token).map(Some);
+        }
+        match self.resolve_upvalue(state - 1, name, token)? {
+            Some(index) => {
+                let source = UpvalueSource {
+                    is_local: false,
+                    index,
+                };
+                self.add_upvalue(state, source, token).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn add_upvalue(
+        &mut self,
+        state: usize,
+        source: UpvalueSource,
+        token: &Token,
+    ) -> Result<u8, NZErrors> {
+        let upvalues = &mut self.states[state].function.upvalues;
+        if let Some(index) = upvalues.iter().position(|upvalue| *upvalue == source) {
+            return Ok(index as u8);
+        }
+        let Ok(index) = u8::try_from(upvalues.len()) else {
+            return Err(NZErrors::CompileError(
+                token.clone(),
+                "Too many closure variables in function.".to_string(),
+            ));
+        };
+        upvalues.push(source);
+        Ok(index)
+    }
+
+    /// Emits a read (`assign` false) or write of the variable `name`.
+    fn named_variable(&mut self, name: &str, token: &Token, assign: bool) -> Result<(), NZErrors> {
+        let state = self.states.len() - 1;
+        let op = if let Some(slot) = self.resolve_local(state, name) {
+            if assign {
+                OpCode::SetLocal(slot)
+            } else {
+                OpCode::GetLocal(slot)
+            }
+        } else if let Some(index) = self.resolve_upvalue(state, name, token)? {
+            if assign {
+                OpCode::SetUpvalue(index)
+            } else {
+                OpCode::GetUpvalue(index)
+            }
+        } else if assign {
+            OpCode::SetGlobal
+        } else {
+            OpCode::GetGlobal
+        };
+        self.emit(op, token);
+        Ok(())
+    }
+
+    fn begin_function(&mut self, name: &str, arity: usize, function_type: FunctionType) {
+        // slot 0 holds the callee, or the receiver for methods
+        let receiver = match function_type {
+            FunctionType::Method | FunctionType::Initializer => "this",
+            FunctionType::Script | FunctionType::Function => "",
+        };
+        self.states.push(FunctionState {
+            function: Function {
+                name: name.to_string(),
+                arity,
+                initializer: function_type == FunctionType::Initializer,
+                ..Function::default()
+            },
+            function_type,
+            locals: vec![Local {
+                name: receiver.to_string(),
+                depth: 0,
+                captured: false,
+            }],
+            scope_depth: 0,
+            loops: Vec::new(),
+        });
+    }
+
+    /// Compiles a function body and emits the instruction that creates its
+    /// closure at runtime.
+    fn function(
+        &mut self,
+        name: &Token,
+        params: &[Token],
+        body: &[Stmt],
+        function_type: FunctionType,
+    ) -> Result<(), NZErrors> {
+        self.begin_function(&name.lexeme, params.len(), function_type);
+        self.begin_scope();
+        for param in params {
+            self.add_local(&param.lexeme, param)?;
+        }
+        for stmt in body {
+            stmt.accept(self)?;
+        }
+        self.emit_return();
+
+        let function = self.finish();
+        let index = self.chunk().add_function(function, name)?;
+        self.emit(OpCode::Closure(index), name);
+        Ok(())
+    }
+
+    /// Leaves the loop body's scopes and emits the jump of a `break` or
+    /// `continue`, to be patched when the loop is finished.
+    fn loop_jump(&mut self, keyword: &Token) -> Result<usize, NZErrors> {
+        let Some(depth) = self.state().loops.last().map(|l| l.scope_depth) else {
+            return Err(NZErrors::CompileError(
+                keyword.clone(),
+                format!("Can't use '{}' outside of a loop.", keyword.lexeme),
+            ));
+        };
+        self.discard_locals(depth);
+        Ok(self.emit_jump(OpCode::Jump))
+    }
+
+    /// Compiles the items of a list or map literal and builds it with
+    /// `build`. A literal with more items than an operand can count is built
+    /// in batches, each one after the first added with `extend`.
+    fn literal<T>(
+        &mut self,
+        items: &[T],
+        token: &Token,
+        mut compile: impl FnMut(&mut Self, &T) -> Result<(), NZErrors>,
+        build: fn(u16) -> OpCode,
+        extend: fn(u16) -> OpCode,
+    ) -> Result<(), NZErrors> {
+        let mut batches = items.chunks(u16::MAX as usize);
+        let mut op = build;
+        // an empty literal still needs its one `build`
+        let mut batch = batches.next().unwrap_or_default();
+        loop {
+            for item in batch {
+                compile(self, item).map_err(|e| e.or_at(token))?;
+            }
+            self.emit(op(batch.len() as u16), token);
+            op = extend;
+            match batches.next() {
+                Some(next) => batch = next,
+                None => return Ok(()),
+            }
+        }
+    }
+}
+
+fn jump_distance(distance: usize) -> Result<u16, NZErrors> {
+    u16::try_from(distance).map_err(|_| {
+        NZErrors::CompileError(Token::default(), "Too much code to jump over.".to_string())
+    })
+}
+
+impl ExprVisitor<Result<(), NZErrors>> for Compiler {
+    fn visit_binary_expr(&mut self, left: &Expr, op: &Token, right: &Expr) -> Result<(), NZErrors> {
+        left.accept(self)?;
+        right.accept(self)?;
+        // errors underline the whole expression, as in the tree-walker
+        let mut token = op.clone();
+        token.span = left.span().to(right.span());
+        self.emit(OpCode::Binary, &token);
+        Ok(())
+    }
+
+    fn visit_grouping_expr(&mut self, expression: &Expr) -> Result<(), NZErrors> {
+        expression.accept(self)
+    }
+
+    fn visit_literal_expr(&mut self, literal: &Literal) -> Result<(), NZErrors> {
+        let token = Token::default();
+        match literal {
+            Literal::Number(n) => self.emit_constant(Object::Number(*n), &token)?,
+            Literal::String(s) => self.emit_constant(Object::String(s.clone()), &token)?,
+            Literal::Boolean(true) => {
+                self.emit(OpCode::True, &token);
+            }
+            Literal::Boolean(false) => {
+                self.emit(OpCode::False, &token);
+            }
+            Literal::Nil => {
+                self.emit(OpCode::Nil, &token);
+            }
+        }
+        Ok(())
+    }
+
+    fn visit_unary_expr(&mut self, op: &Token, expr: &Expr) -> Result<(), NZErrors> {
+        expr.accept(self)?;
+        let mut token = op.clone();
+        token.span = op.span.to(expr.span());
+        self.emit(OpCode::Unary, &token);
+        Ok(())
+    }
+
+    fn visit_variable_expr(&mut self, name: &Token) -> Result<(), NZErrors> {
+        self.named_variable(&name.lexeme, name, false)
+    }
+
+    fn visit_assign_expr(&mut self, name: &Token, value: &Expr) -> Result<(), NZErrors> {
+        value.accept(self)?;
+        self.named_variable(&name.lexeme, name, true)
+    }
+
+    fn visit_logical_expr(
+        &mut self,
+        left: &Expr,
+        op: &Token,
+        right: &Expr,
+    ) -> Result<(), NZErrors> {
+        left.accept(self)?;
+        let end_jump = if op.token_type == TokenType::OR {
+            let else_jump = self.emit_jump(OpCode::JumpIfFalse);
+            let end_jump = self.emit_jump(OpCode::Jump);
+            self.patch_jump(else_jump)?;
+            end_jump
+        } else {
+            self.emit_jump(OpCode::JumpIfFalse)
+        };
+        self.emit(OpCode::Pop, op);
+        right.accept(self)?;
+        self.patch_jump(end_jump)
+    }
+
+    fn visit_call_expr(
+        &mut self,
+        callee: &Expr,
+        paren: &Token,
+        arguments: &[Expr],
+    ) -> Result<(), NZErrors> {
+        callee.accept(self)?;
+        for arg in arguments {
+            arg.accept(self)?;
+        }
+        // the parser allows at most 255 arguments
+        self.emit(OpCode::Call(arguments.len() as u8), paren);
+        Ok(())
+    }
+
+    fn visit_get_expr(&mut self, object: &Expr, name: &Token) -> Result<(), NZErrors> {
+        object.accept(self)?;
+        self.emit(OpCode::GetProperty, name);
+        Ok(())
+    }
+
+    fn visit_set_expr(
+        &mut self,
+        object: &Expr,
+        name: &Token,
+        value: &Expr,
+    ) -> Result<(), NZErrors> {
+        object.accept(self)?;
+        value.accept(self)?;
+        self.emit(OpCode::SetProperty, name);
+        Ok(())
+    }
+
+    fn visit_compound_set_expr(
+        &mut self,
+        object: &Expr,
+        name: &Token,
+        op: &Token,
+        value: &Expr,
+    ) -> Result<(), NZErrors> {
+        // the instance stays below the current value for SetProperty
+        object.accept(self)?;
+        self.emit(OpCode::Dup, name);
+        self.emit(OpCode::GetProperty, name);
+        value.accept(self)?;
+        let mut token = op.clone();
+        token.span = object.span().to(value.span());
+        self.emit(OpCode::Binary, &token);
+        self.emit(OpCode::SetProperty, name);
+        Ok(())
+    }
+
+    fn visit_this_expr(&mut self, name: &Token) -> Result<(), NZErrors> {
+        self.named_variable("this", name, false)
+    }
+
+    fn visit_super_expr(&mut self, name: &Token, method: &Token) -> Result<(), NZErrors> {
+        self.named_variable("this", name, false)?;
+        self.named_variable("super", name, false)?;
+        self.emit(OpCode::GetSuper, method);
+        Ok(())
+    }
+
+    fn visit_list_expr(&mut self, bracket: &Token, elements: &[Expr]) -> Result<(), NZErrors> {
+        self.literal(
+            elements,
+            bracket,
+            |compiler, element| element.accept(compiler),
+            OpCode::List,
+            OpCode::ExtendList,
+        )
+    }
+
+    fn visit_map_expr(&mut self, brace: &Token, entries: &[(Expr, Expr)]) -> Result<(), NZErrors> {
+        self.literal(
+            entries,
+            brace,
+            |compiler, (key, value)| {
+                key.accept(compiler)?;
+                value.accept(compiler)
+            },
+            OpCode::Map,
+            OpCode::ExtendMap,
+        )
+    }
+
+    fn visit_index_expr(
+        &mut self,
+        object: &Expr,
+        bracket: &Token,
+        index: &Expr,
+    ) -> Result<(), NZErrors> {
+        object.accept(self)?;
+        index.accept(self)?;
+        let mut token = bracket.clone();
+        token.span = object.span().to(bracket.span);
+        self.emit(OpCode::GetIndex, &token);
+        Ok(())
+    }
+
+    fn visit_index_set_expr(
+        &mut self,
+        object: &Expr,
+        bracket: &Token,
+        index: &Expr,
+        value: &Expr,
+    ) -> Result<(), NZErrors> {
+        object.accept(self)?;
+        index.accept(self)?;
+        value.accept(self)?;
+        let mut token = bracket.clone();
+        token.span = object.span().to(bracket.span);
+        self.emit(OpCode::SetIndex, &token);
+        Ok(())
+    }
+
+    fn visit_lambda_expr(
+        &mut self,
+        keyword: &Token,
+        params: &[Token],
+        body: &[Stmt],
+    ) -> Result<(), NZErrors> {
+        let name = Token {
+            lexeme: "lambda".to_string(),
+            ..keyword.clone()
+        };
+        self.function(&name, params, body, FunctionType::Function)
+    }
+
+    fn visit_interpolate_expr(&mut self, expr: &Expr) -> Result<(), NZErrors> {
+        expr.accept(self)?;
+        self.emit(OpCode::Stringify, &Token::default());
+        Ok(())
+    }
+}
+
+impl StmtVisitor<Result<(), NZErrors>> for Compiler {
+    fn visit_expression_stmt(&mut self, expression: &Expr) -> Result<(), NZErrors> {
+        expression.accept(self)?;
+        self.emit(OpCode::Pop, &Token::default());
+        Ok(())
+    }
+
+    fn visit_print_stmt(&mut self, expression: &Expr) -> Result<(), NZErrors> {
+        expression.accept(self)?;
+        self.emit(OpCode::Print, &Token::default());
+        Ok(())
+    }
+
+    fn visit_var_stmt(&mut self, name: &Token, initializer: &Option<Expr>) -> Result<(), NZErrors> {
+        match initializer {
+            Some(initializer) => initializer.accept(self)?,
+            None => {
+                self.emit(OpCode::Nil, name);
+            }
+        }
+        self.define_variable(name)
+    }
+
+    fn visit_block_stmt(&mut self, statements: &[Stmt]) -> Result<(), NZErrors> {
+        self.begin_scope();
+        for stmt in statements {
+            stmt.accept(self)?;
+        }
+        self.end_scope();
+        Ok(())
+    }
+
+    fn visit_if_stmt(
+        &mut self,
+        condition: &Expr,
+        then_branch: &Stmt,
+        else_branch: &Option<Box<Stmt>>,
+    ) -> Result<(), NZErrors> {
+        condition.accept(self)?;
+        let then_jump = self.emit_jump(OpCode::JumpIfFalse);
+        self.emit(OpCode::Pop, &Token::default());
+        then_branch.accept(self)?;
+        let else_jump = self.emit_jump(OpCode::Jump);
+
+        self.patch_jump(then_jump)?;
+        self.emit(OpCode::Pop, &Token::default());
+        if let Some(else_branch) = else_branch {
+            else_branch.accept(self)?;
+        }
+        self.patch_jump(else_jump)
+    }
+
+    fn visit_while_stmt(
+        &mut self,
+        condition: &Expr,
+        body: &Stmt,
+        increment: &Option<Expr>,
+    ) -> Result<(), NZErrors> {
+        let loop_start = self.chunk().code.len();
+        condition.accept(self)?;
+        let exit_jump = self.emit_jump(OpCode::JumpIfFalse);
+        self.emit(OpCode::Pop, &Token::default());
+
+        let scope_depth = self.state().scope_depth;
+        self.state().loops.push(Loop {
+            scope_depth,
+            breaks: Vec::new(),
+            continues: Vec::new(),
+        });
+        body.accept(self)?;
+        let finished = self.state().loops.pop().expect("loop was pushed above");
+
+        self.patch_jumps_to_here(finished.continues)?;
+        if let Some(increment) = increment {
+            increment.accept(self)?;
+            self.emit(OpCode::Pop, &Token::default());
+        }
+        self.emit_loop(loop_start)?;
+
+        self.patch_jump(exit_jump)?;
+        self.emit(OpCode::Pop, &Token::default());
+        // `break` lands after the condition's pop: it never pushed one
+        self.patch_jumps_to_here(finished.breaks)
+    }
+
+    fn visit_break_stmt(&mut self, keyword: &Token) -> Result<(), NZErrors> {
+        let jump = self.loop_jump(keyword)?;
+        self.state().loops.last_mut().unwrap().breaks.push(jump);
+        Ok(())
+    }
+
+    fn visit_continue_stmt(&mut self, keyword: &Token) -> Result<(), NZErrors> {
+        let jump = self.loop_jump(keyword)?;
+        self.state().loops.last_mut().unwrap().continues.push(jump);
+        Ok(())
+    }
+
+    fn visit_function_stmt(
+        &mut self,
+        name: &Token,
+        params: &[Token],
+        body: &[Stmt],
+    ) -> Result<(), NZErrors> {
+        if self.state().scope_depth > 0 {
+            // declared before the body so the function can call itself
+            self.add_local(&name.lexeme, name)?;
+            self.function(name, params, body, FunctionType::Function)
+        } else {
+            self.function(name, params, body, FunctionType::Function)?;
+            self.define_variable(name)
+        }
+    }
+
+    fn visit_return_stmt(&mut self, keyword: &Token, value: &Option<Expr>) -> Result<(), NZErrors> {
+        match value {
+            Some(value) => value.accept(self)?,
+            None if self.state().function_type == FunctionType::Initializer => {
+                self.emit(OpCode::GetLocal(0), keyword);
+            }
+            None => {
+                self.emit(OpCode::Nil, keyword);
+            }
+        }
+        self.emit(OpCode::Return, keyword);
+        Ok(())
+    }
+
+    fn visit_class_stmt(
+        &mut self,
+        name: &Token,
+        superclass: &Option<Expr>,
+        methods: &[Stmt],
+    ) -> Result<(), NZErrors> {
+        // a local class gets its slot up front so its methods can refer to it
+        let slot = if self.state().scope_depth > 0 {
+            let slot = self.state().locals.len() as u8;
+            self.emit(OpCode::Nil, name);
+            self.add_local(&name.lexeme, name)?;
+            Some(slot)
+        } else {
+            None
+        };
+
+        if let Some(superclass) = superclass {
+            superclass.accept(self)?;
+            // the superclass stays on the stack as the local "super"
+            self.begin_scope();
+            self.add_local("super", name)?;
+        }
+
+        let Ok(count) = u8::try_from(methods.len()) else {
+            return Err(NZErrors::CompileError(
+                name.clone(),
+                "Too many methods in one class.".to_string(),
+            ));
+        };
+        for method in methods {
+            if let Stmt::Function {
+                name, params, body, ..
+            } = method
+            {
+                let function_type = if name.lexeme == "init" {
+                    FunctionType::Initializer
+                } else {
+                    FunctionType::Method
+                };
+                self.function(name, params, body, function_type)?;
+            }
+        }
+        self.emit(OpCode::Class(count, superclass.is_some()), name);
+
+        match slot {
+            Some(slot) => {
+                self.emit(OpCode::SetLocal(slot), name);
+                self.emit(OpCode::Pop, name);
+            }
+            None => {
+                self.emit(OpCode::DefineGlobal, name);
+            }
+        }
+
+        if superclass.is_some() {
+            self.end_scope();
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,96 @@
+use core::fmt;
+use std::{cell::RefCell, rc::Rc};
+
+use crate::{
+    error::NZErrors,
+    interpreter::{
+        loxcallable::{Arity, LoxCallable},
+        loxclass::LoxMethod,
+        loxinstance::LoxInstance,
+        Interpreter,
+    },
+    object::Object,
+};
+
+use super::{chunk::Chunk, Vm};
+
+/// Where a closure finds one of its captured variables when it is created:
+/// a slot of the enclosing function, or one of the enclosing closure's own
+/// upvalues.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UpvalueSource {
+    pub is_local: bool,
+    pub index: u8,
+}
+
+/// A function as produced by the compiler, before it captures anything.
+#[derive(Debug, Default)]
+pub struct Function {
+    pub name: String,
+    pub arity: usize,
+    pub initializer: bool,
+    pub upvalues: Vec<UpvalueSource>,
+    pub chunk: Chunk,
+}
+
+/// A captured variable. It points into the VM stack while the variable's
+/// function is running and holds the value itself once that returns.
+#[derive(Debug)]
+pub enum Upvalue {
+    Open(usize),
+    Closed(Object),
+}
+
+#[derive(Debug, Clone)]
+pub struct Closure {
+    pub function: Rc<Function>,
+    pub upvalues: Vec<Rc<RefCell<Upvalue>>>,
+}
+
+/// A method closure together with the instance it was looked up on.
+#[derive(Debug)]
+pub struct BoundMethod {
+    pub receiver: Object,
+    pub method: Rc<Closure>,
+}
+
+impl LoxCallable for Closure {
+    fn arity(&self) -> Arity {
+        Arity::Fixed(self.function.arity)
+    }
+
+    fn call(&self, interpreter: &mut Interpreter, args: &[Object]) -> Result<Object, NZErrors> {
+        Vm::new(interpreter).call(Rc::new(self.clone()), Object::Nill, args)
+    }
+}
+
+impl LoxMethod for Closure {
+    fn bind(&self, instance: Rc<RefCell<LoxInstance>>) -> Rc<dyn LoxCallable> {
+        Rc::new(BoundMethod {
+            receiver: Object::Instance(instance),
+            method: Rc::new(self.clone()),
+        })
+    }
+}
+
+impl LoxCallable for BoundMethod {
+    fn arity(&self) -> Arity {
+        self.method.arity()
+    }
+
+    fn call(&self, interpreter: &mut Interpreter, args: &[Object]) -> Result<Object, NZErrors> {
+        Vm::new(interpreter).call(Rc::clone(&self.method), self.receiver.clone(), args)
+    }
+}
+
+impl fmt::Display for Closure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<fn {}>", self.function.name)
+    }
+}
+
+impl fmt::Display for BoundMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.method)
+    }
+}
@@ -0,0 +1,540 @@
+//! Bytecode backend: `Compiler` turns the resolved AST into a `Function`
+//! and `Vm` runs it on a value stack. It shares `Object`, the globals and
+//! the natives with the tree-walking `Interpreter`, so the two backends are
+//! interchangeable for any program.
+
+pub mod chunk;
+pub mod compiler;
+pub mod function;
+
+use std::{
+    any::Any,
+    cell::RefCell,
+    collections::{BTreeMap, HashMap},
+    rc::Rc,
+};
+
+pub use compiler::Compiler;
+use function::{BoundMethod, Closure, Function, Upvalue};
+
+use crate::{
+    error::NZErrors,
+    interpreter::{
+        loxcallable::LoxCallable,
+        loxclass::{LoxClass, LoxMethod},
+        loxinstance::LoxInstance,
+        operators, Interpreter, MAX_CALL_DEPTH,
+    },
+    object::{MapKey, Object},
+    token::Token,
+};
+use chunk::OpCode;
+
+/// The VM's value stack. It lives in the `Interpreter` so that a closure
+/// called back from a native function keeps running on the same stack, where
+/// its open upvalues point.
+#[derive(Debug, Default)]
+pub struct Stack {
+    values: Vec<Object>,
+    // upvalues still pointing into `values`
+    open_upvalues: Vec<Rc<RefCell<Upvalue>>>,
+}
+
+struct CallFrame {
+    closure: Rc<Closure>,
+    ip: usize,
+    // stack index of slot 0: the callee, or the receiver of a method
+    base: usize,
+}
+
+/// Runs compiled scripts against an `Interpreter`'s globals. Values defined
+/// by either backend can be used from the other:
+///
+/// ```
+/// use interpreter::{parser::Parser, resolver::Resolver, scanner::Scanner};
+/// use interpreter::vm::{Compiler, Vm};
+/// use interpreter::{Interpreter, Object};
+///
+/// let mut interpreter = Interpreter::new();
+/// interpreter
+///     .run("class Point { init(x) { this.x = x; } }")
+///     .unwrap();
+///
+/// let source = "var x = Point(3).x;";
+/// let tokens = Scanner::new(source.to_string()).scan_tokens().unwrap();
+/// let statements = Parser::new(tokens).parse().unwrap();
+/// Resolver::new(&mut interpreter).resolve(&statements).unwrap();
+/// let script = Compiler::compile(&statements).unwrap();
+/// Vm::new(&mut interpreter).interpret(script).unwrap();
+/// let x = interpreter.get_global("x");
+/// assert!(matches!(x, Some(Object::Number(n)) if n == 3.0));
+/// ```
+pub struct Vm<'a> {
+    // calls already running when the VM started, e.g. the tree-walker
+    // functions that led to a call of a compiled closure
+    base_depth: usize,
+    interpreter: &'a mut Interpreter,
+    frames: Vec<CallFrame>,
+}
+
+impl<'a> Vm<'a> {
+    pub fn new(interpreter: &'a mut Interpreter) -> Self {
+        Self {
+            base_depth: interpreter.depth,
+            interpreter,
+            frames: Vec::new(),
+        }
+    }
+
+    /// Runs a compiled script and returns the value it returns.
+    pub fn interpret(&mut self, script: Rc<Function>) -> Result<Object, NZErrors> {
+        // anything left over is from a run that ended in an error
+        self.interpreter.stack.values.clear();
+        self.interpreter.stack.open_upvalues.clear();
+        let closure = Rc::new(Closure {
+            function: script,
+            upvalues: Vec::new(),
+        });
+        self.call(closure, Object::Nill, &[])
+    }
+
+    /// Calls `closure` with `receiver` in slot 0 and runs it to completion.
+    pub fn call(
+        &mut self,
+        closure: Rc<Closure>,
+        receiver: Object,
+        args: &[Object],
+    ) -> Result<Object, NZErrors> {
+        let start = self.stack().len();
+        self.push(receiver);
+        self.stack().extend_from_slice(args);
+        let result = self
+            .push_frame(closure, args.len(), &Token::default())
+            .and_then(|_| self.run());
+        if result.is_err() {
+            self.close_upvalues(start);
+            self.stack().truncate(start);
+        }
+        result
+    }
+
+    fn stack(&mut self) -> &mut Vec<Object> {
+        &mut self.interpreter.stack.values
+    }
+
+    fn push(&mut self, value: Object) {
+        self.stack().push(value);
+    }
+
+    fn pop(&mut self) -> Object {
+        self.stack().pop().expect("VM stack underflow")
+    }
+
+    fn peek(&mut self, distance: usize) -> &Object {
+        let stack = self.stack();
+        &stack[stack.len() - 1 - distance]
+    }
+
+    fn frame(&mut self) -> &mut CallFrame {
+        self.frames.last_mut().expect("no call frame")
+    }
+
+    fn run(&mut self) -> Result<Object, NZErrors> {
+        loop {
+            let frame = self.frame();
+            let closure = Rc::clone(&frame.closure);
+            let base = frame.base;
+            let ip = frame.ip;
+            frame.ip += 1;
+
+            let chunk = &closure.function.chunk;
+            let token = &chunk.tokens[ip];
+            let error = |message: String| NZErrors::RuntimeError(token.clone(), message);
+
+            match chunk.code[ip] {
+                OpCode::Constant(index) => self.push(chunk.constants[index as usize].clone()),
+                OpCode::Nil => self.push(Object::Nill),
+                OpCode::True => self.push(Object::Boolean(true)),
+                OpCode::False => self.push(Object::Boolean(false)),
+                OpCode::Pop => {
+                    self.pop();
+                }
+                OpCode::Dup => {
+                    let value = self.peek(0).clone();
+                    self.push(value);
+                }
+                OpCode::GetLocal(slot) => {
+                    let value = self.stack()[base + slot as usize].clone();
+                    self.push(value);
+                }
+                OpCode::SetLocal(slot) => {
+                    let value = self.peek(0).clone();
+                    self.stack()[base + slot as usize] = value;
+                }
+                OpCode::GetUpvalue(index) => {
+                    let value = match &*closure.upvalues[index as usize].borrow() {
+                        Upvalue::Open(slot) => self.stack()[*slot].clone(),
+                        Upvalue::Closed(value) => value.clone(),
+                    };
+                    self.push(value);
+                }
+                OpCode::SetUpvalue(index) => {
+                    let value = self.peek(0).clone();
+                    match &mut *closure.upvalues[index as usize].borrow_mut() {
+                        Upvalue::Open(slot) => self.stack()[*slot] = value,
+                        Upvalue::Closed(closed) => *closed = value,
+                    }
+                }
+                OpCode::GetGlobal => {
+                    let value = self.interpreter.globals.borrow().get(token)?;
+                    self.push(value);
+                }
+                OpCode::DefineGlobal => {
+                    let value = self.pop();
+                    self.interpreter
+                        .globals
+                        .borrow_mut()
+                        .define(token.lexeme.clone(), value);
+                }
+                OpCode::SetGlobal => {
+                    let value = self.peek(0).clone();
+                    self.interpreter.globals.borrow_mut().assign(token, value)?;
+                }
+                OpCode::GetProperty => {
+                    let object = self.pop();
+                    let value = self.interpreter.get_property(object, token)?;
+                    self.push(value);
+                }
+                OpCode::SetProperty => {
+                    let value = self.pop();
+                    match self.pop() {
+                        Object::Instance(instance) => {
+                            instance.borrow_mut().set(token, value.clone());
+                            self.push(value);
+                        }
+                        _ => return Err(error("Only instances have fields.".to_string())),
+                    }
+                }
+                OpCode::GetSuper => {
+                    let superclass = self.pop();
+                    let (Object::Class(superclass), Object::Instance(instance)) =
+                        (superclass, self.pop())
+                    else {
+                        return Err(error("Superclass must be a class.".to_string()));
+                    };
+                    let method = superclass
+                        .find_method(&token.lexeme)
+                        .ok_or_else(|| error(format!("Undefined property '{}'.", token.lexeme)))?;
+                    self.push(Object::Callable(method.bind(instance)));
+                }
+                OpCode::GetIndex => {
+                    let index = self.pop();
+                    let object = self.pop();
+                    let value = operators::get_index(&object, &index, token)?;
+                    self.push(value);
+                }
+                OpCode::SetIndex => {
+                    let value = self.pop();
+                    let index = self.pop();
+                    let object = self.pop();
+                    operators::set_index(&object, &index, value.clone(), token)?;
+                    self.push(value);
+                }
+                OpCode::Unary => {
+                    let right = self.pop();
+                    self.push(operators::unary(token, &right)?);
+                }
+                OpCode::Binary => {
+                    let right = self.pop();
+                    let left = self.pop();
+                    self.push(operators::binary(&left, token, &right)?);
+                }
+                OpCode::Print => println!("{}", self.pop()),
+                OpCode::Stringify => {
+                    let value = self.pop();
+                    self.push(Object::String(value.to_string()));
+                }
+                OpCode::Jump(distance) => self.frame().ip += distance as usize,
+                OpCode::JumpIfFalse(distance) => {
+                    if !operators::is_truthy(self.peek(0)) {
+                        self.frame().ip += distance as usize;
+                    }
+                }
+                OpCode::Loop(distance) => self.frame().ip -= distance as usize,
+                OpCode::Call(count) => {
+                    let callee = self.peek(count as usize).clone();
+                    self.call_value(callee, count as usize, token)?;
+                }
+                OpCode::Closure(index) => {
+                    let function = Rc::clone(&chunk.functions[index as usize]);
+                    let upvalues = function
+                        .upvalues
+                        .iter()
+                        .map(|source| {
+                            if source.is_local {
+                                self.capture_upvalue(base + source.index as usize)
+                            } else {
+                                Rc::clone(&closure.upvalues[source.index as usize])
+                            }
+                        })
+                        .collect();
+                    self.push(Object::Callable(Rc::new(Closure { function, upvalues })));
+                }
+                OpCode::CloseUpvalue => {
+                    let top = self.stack().len() - 1;
+                    self.close_upvalues(top);
+                    self.pop();
+                }
+                OpCode::Return => {
+                    let result = self.pop();
+                    self.close_upvalues(base);
+                    self.stack().truncate(base);
+                    self.frames.pop();
+                    if self.frames.is_empty() {
+                        return Ok(result);
+                    }
+                    self.push(result);
+                }
+                OpCode::Class(count, inherits) => {
+                    let first = self.stack().len() - count as usize;
+                    let mut methods = HashMap::new();
+                    for method in self.stack().split_off(first) {
+                        if let Object::Callable(method) = &method
+                            && let Some(method) = as_closure(method)
+                        {
+                            let name = method.function.name.clone();
+                            methods.insert(name, method as Rc<dyn LoxMethod>);
+                        }
+                    }
+                    let superclass = if inherits {
+                        match self.peek(0) {
+                            Object::Class(superclass) => Some(Rc::clone(superclass)),
+                            _ => return Err(error("Superclass must be a class.".to_string())),
+                        }
+                    } else {
+                        None
+                    };
+                    let class = LoxClass::new(token.clone(), superclass, methods);
+                    self.push(Object::Class(Rc::new(class)));
+                }
+                OpCode::List(count) => {
+                    let elements = self.pop_many(count as usize);
+                    self.push(Object::List(Rc::new(RefCell::new(elements))));
+                }
+                OpCode::Map(count) => {
+                    let entries = self.pop_many(2 * count as usize);
+                    let mut map = BTreeMap::new();
+                    insert_entries(&mut map, entries).map_err(error)?;
+                    self.push(Object::Map(Rc::new(RefCell::new(map))));
+                }
+                OpCode::ExtendList(count) => {
+                    let elements = self.pop_many(count as usize);
+                    if let Some(Object::List(list)) = self.stack().last() {
+                        list.borrow_mut().extend(elements);
+                    }
+                }
+                OpCode::ExtendMap(count) => {
+                    let entries = self.pop_many(2 * count as usize);
+                    if let Some(Object::Map(map)) = self.stack().last() {
+                        insert_entries(&mut map.borrow_mut(), entries).map_err(error)?;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Pops the top `count` values, in the order they were pushed.
+    fn pop_many(&mut self, count: usize) -> Vec<Object> {
+        let first = self.stack().len() - count;
+        self.stack().split_off(first)
+    }
+
+    /// Calls the value `count` slots below the top of the stack with the
+    /// `count` values above it as arguments.
+    fn call_value(&mut self, callee: Object, count: usize, paren: &Token) -> Result<(), NZErrors> {
+        let arity = match &callee {
+            Object::Callable(callable) => callable.arity(),
+            Object::Class(class) => class.arity(),
+            _ => {
+                return Err(NZErrors::RuntimeError(
+                    paren.clone(),
+                    "Can only call functions and classes.".to_string(),
+                ));
+            }
+        };
+        if !arity.accepts(count) {
+            return Err(NZErrors::RuntimeError(
+                paren.clone(),
+                format!("Expected {} arguments but got {}.", arity, count),
+            ));
+        }
+        let base = self.stack().len() - count - 1;
+
+        match callee {
+            Object::Callable(callable) => {
+                if let Some(closure) = as_closure(&callable) {
+                    return self.push_frame(closure, count, paren);
+                }
+                let any: Rc<dyn Any> = Rc::clone(&callable) as Rc<dyn Any>;
+                if let Ok(bound) = any.downcast::<BoundMethod>() {
+                    self.stack()[base] = bound.receiver.clone();
+                    return self.push_frame(Rc::clone(&bound.method), count, paren);
+                }
+                // natives, and callables the embedder registered
+                let args = self.stack().split_off(base + 1);
+                self.pop();
+                let result = self
+                    .call_out(|interpreter| callable.call(interpreter, &args))
+                    .map_err(|e| e.or_at(paren))?;
+                self.push(result);
+                Ok(())
+            }
+            Object::Class(class) => {
+                let initializer = class
+                    .find_method("init")
+                    .map(|initializer| (initializer as Rc<dyn Any>).downcast::<Closure>());
+                if let Some(Err(_)) = initializer {
+                    // a class the tree-walker defined in the shared globals
+                    let args = self.stack().split_off(base + 1);
+                    self.pop();
+                    let instance = self
+                        .call_out(|interpreter| LoxClass::call(&class, interpreter, &args))
+                        .map_err(|e| e.or_at(paren))?;
+                    self.push(instance);
+                    return Ok(());
+                }
+                let instance = Rc::new(RefCell::new(LoxInstance::new(&class)));
+                self.stack()[base] = Object::Instance(instance);
+                match initializer {
+                    Some(Ok(closure)) => self.push_frame(closure, count, paren),
+                    _ => Ok(()),
+                }
+            }
+            _ => unreachable!("checked above"),
+        }
+    }
+
+    /// Runs a call the interpreter makes on the VM's behalf, with the VM's
+    /// frames counted in its depth.
+    fn call_out(
+        &mut self,
+        call: impl FnOnce(&mut Interpreter) -> Result<Object, NZErrors>,
+    ) -> Result<Object, NZErrors> {
+        let depth = self.base_depth + self.frames.len();
+        let outer = std::mem::replace(&mut self.interpreter.depth, depth);
+        let result = call(self.interpreter);
+        self.interpreter.depth = outer;
+        result
+    }
+
+    fn push_frame(
+        &mut self,
+        closure: Rc<Closure>,
+        count: usize,
+        paren: &Token,
+    ) -> Result<(), NZErrors> {
+        if self.base_depth + self.frames.len() == MAX_CALL_DEPTH {
+            return Err(NZErrors::RuntimeError(
+                paren.clone(),
+                "Stack overflow.".to_string(),
+            ));
+        }
+        let base = self.stack().len() - count - 1;
+        self.frames.push(CallFrame {
+            closure,
+            ip: 0,
+            base,
+        });
+        Ok(())
+    }
+
+    fn capture_upvalue(&mut self, slot: usize) -> Rc<RefCell<Upvalue>> {
+        let open_upvalues = &mut self.interpreter.stack.open_upvalues;
+        let existing = open_upvalues
+            .iter()
+            .find(|upvalue| matches!(*upvalue.borrow(), Upvalue::Open(s) if s == slot));
+        if let Some(upvalue) = existing {
+            return Rc::clone(upvalue);
+        }
+        let upvalue = Rc::new(RefCell::new(Upvalue::Open(slot)));
+        open_upvalues.push(Rc::clone(&upvalue));
+        upvalue
+    }
+
+    /// Moves the values of all upvalues pointing at `from` or above off the
+    /// stack, before those slots are popped.
+    fn close_upvalues(&mut self, from: usize) {
+        let Stack {
+            values,
+            open_upvalues,
+        } = &mut self.interpreter.stack;
+        open_upvalues.retain(|upvalue| {
+            let slot = match &*upvalue.borrow() {
+                Upvalue::Open(slot) => *slot,
+                Upvalue::Closed(_) => return false,
+            };
+            if slot < from {
+                return true;
+            }
+            *upvalue.borrow_mut() = Upvalue::Closed(values[slot].clone());
+            false
+        });
+    }
+}
+
+/// The VM closure behind `callable`, if it is one.
+fn as_closure(callable: &Rc<dyn LoxCallable>) -> Option<Rc<Closure>> {
+    (Rc::clone(callable) as Rc<dyn Any>).downcast().ok()
+}
+
+/// Inserts `entries`, alternating keys and values, into `map`.
+fn insert_entries(map: &mut BTreeMap<MapKey, Object>, entries: Vec<Object>) -> Result<(), String> {
+    let mut entries = entries.into_iter();
+    while let (Some(key), Some(value)) = (entries.next(), entries.next()) {
+        map.insert(MapKey::from_object(&key)?, value);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use super::*;
+    use crate::{parser::Parser, resolver::Resolver, scanner::Scanner};
+
+    fn run_compiled(interpreter: &mut Interpreter, source: &str) -> Result<Object, NZErrors> {
+        let tokens = Scanner::new(source.to_string()).scan_tokens()?;
+        let statements = Parser::new(tokens).parse().map_err(|mut e| e.remove(0))?;
+        Resolver::new(interpreter).resolve(&statements)?;
+        Vm::new(interpreter).interpret(Compiler::compile(&statements)?)
+    }
+
+    #[test]
+    fn recursion_through_both_backends_overflows() {
+        // the tree-walker half needs the native stack `main` gives it
+        let overflow = thread::Builder::new()
+            .stack_size(256 * 1024 * 1024)
+            .spawn(|| {
+                let mut interpreter = Interpreter::new();
+                interpreter
+                    .run("fun walk(n) { if (n == 0) return 0; return 1 + compiled(n - 1); }")
+                    .unwrap();
+                run_compiled(
+                    &mut interpreter,
+                    "fun compiled(n) { if (n == 0) return 0; return 1 + walk(n - 1); }",
+                )
+                .unwrap();
+
+                run_compiled(&mut interpreter, "var depth = walk(4000);").unwrap();
+                let depth = interpreter.get_global("depth");
+                assert!(matches!(depth, Some(Object::Number(n)) if n == 4000.0));
+                let error = run_compiled(&mut interpreter, "walk(5000);").unwrap_err();
+                (error.message().to_string(), interpreter.depth)
+            })
+            .unwrap()
+            .join()
+            .unwrap();
+        assert_eq!(overflow, ("Stack overflow.".to_string(), 0));
+    }
+}
@@ -0,0 +1,106 @@
+//! Runs the `interpreter` binary the way a user would, for the behavior the
+//! `.nz` corpus can't reach: the REPL, which reads its input from stdin, and
+//! the debug flags.
+
+use std::{
+    fs,
+    io::Write,
+    path::Path,
+    process::{Command, Output, Stdio},
+};
+
+const BACKENDS: [&[&str]; 2] = [&[], &["--vm"]];
+
+fn run(args: &[&str], stdin: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_interpreter"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("interpreter binary");
+    child
+        .stdin
+        .take()
+        .expect("stdin")
+        .write_all(stdin.as_bytes())
+        .expect("write stdin");
+    child.wait_with_output().expect("interpreter output")
+}
+
+/// Feeds `input` to the REPL on both backends and checks what it prints.
+/// Each prompt (`> `, or `... ` inside an open block) is followed by what
+/// that input printed; the session ends with a newline at end of input.
+fn repl(input: &str, expected: &str) -> Vec<String> {
+    let mut stderrs = Vec::new();
+    for args in BACKENDS {
+        let output = run(args, input);
+        assert!(output.status.success(), "{:?}: {:?}", args, output);
+        assert_eq!(
+            String::from_utf8_lossy(&output.stdout),
+            expected,
+            "{:?}",
+            args
+        );
+        stderrs.push(String::from_utf8_lossy(&output.stderr).into_owned());
+    }
+    stderrs
+}
+
+#[test]
+fn repl_echoes_bare_expressions() {
+    let stderrs = repl(
+        "1 + 2\nvar x = 4;\nx * 2\n\"a\" + \"b\"\n",
+        "> 3\n> > 8\n> ab\n> \n",
+    );
+    for stderr in stderrs {
+        assert_eq!(stderr, "");
+    }
+}
+
+#[test]
+fn repl_continues_open_blocks() {
+    let input = "fun f() {\n  if (true) {\n    return 7;\n  }\n}\nf()\nclass A {}\nA\n";
+    repl(input, "> ... ... ... ... > 7\n> > Class A\n> \n");
+}
+
+#[test]
+fn repl_recovers_after_errors() {
+    let input = "1 +;\nprint 5;\nnil + 1;\n\"open\nmissing\nvar y = \"ok\";\ny\n";
+    for stderr in repl(input, "> > 5\n> > > > > ok\n> \n") {
+        let errors: Vec<&str> = stderr
+            .lines()
+            .filter(|line| line.contains(" Error"))
+            .collect();
+        assert_eq!(
+            errors,
+            [
+                "Parse Error at line 1, column 4: Expect expression, got ;",
+                "Runtime Error at line 1, column 5: Cannot add two different types",
+                "Parse Error at line 1, column 1: Unterminated string.",
+                "Runtime Error at line 1, column 1: Undefined variable 'missing'.",
+            ]
+        );
+    }
+}
+
+#[test]
+fn dump_flags_write_to_stderr_and_still_run() {
+    let path = Path::new(env!("CARGO_TARGET_TMPDIR")).join("dump.nz");
+    fs::write(&path, "print 1 + 2;\n").expect("write script");
+    let path = path.to_str().expect("utf-8 path");
+
+    for (flag, dump) in [
+        ("--dump-source", "Content: \"print 1 + 2;\\n\""),
+        ("--dump-tokens", "Tokens: ["),
+        ("--dump-ast", "Expr: ["),
+    ] {
+        for args in BACKENDS {
+            let output = run(&[args, &[flag, path]].concat(), "");
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            assert!(output.status.success(), "{} {:?}: {}", flag, args, stderr);
+            assert_eq!(String::from_utf8_lossy(&output.stdout), "3\n", "{}", flag);
+            assert!(stderr.starts_with(dump), "{} {:?}: {}", flag, args, stderr);
+        }
+    }
+}
@@ -0,0 +1,189 @@
+//! Runs every `.nz` script under `tests/` on both backends and checks it
+//! against its annotations:
+//!
+//! - `// expect: <text>` is a line the script prints to stdout, in order.
+//! - `// parse error: <message>`, `// resolve error: <message>`,
+//!   `// compile error: <message>` and `// runtime error: <message>` are
+//!   errors reported on stderr, in order. Only the kind and message are
+//!   compared, since errors at end of input have no line to annotate.
+//!
+//! The exit code has to agree with the first error: 69 for runtime errors,
+//! 1 for anything reported before the program runs, 0 without errors.
+//!
+//! Each run starts in a fresh scratch directory that holds only an empty
+//! subdirectory `dir`, so scripts can use relative paths for the files they
+//! write.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+const KINDS: [(&str, &str); 4] = [
+    ("parse error", "Parse Error"),
+    ("resolve error", "Resolve Error"),
+    ("compile error", "Compile Error"),
+    ("runtime error", "Runtime Error"),
+];
+
+struct Expected {
+    output: Vec<String>,
+    errors: Vec<(String, String)>,
+}
+
+fn scripts(dir: &Path, found: &mut Vec<PathBuf>) {
+    let mut entries: Vec<PathBuf> = fs::read_dir(dir)
+        .unwrap_or_else(|e| panic!("can't read {}: {}", dir.display(), e))
+        .map(|entry| entry.expect("directory entry").path())
+        .collect();
+    entries.sort();
+    for path in entries {
+        if path.is_dir() {
+            scripts(&path, found);
+        } else if path.extension().is_some_and(|extension| extension == "nz") {
+            found.push(path);
+        }
+    }
+}
+
+fn expected(source: &str) -> Expected {
+    let mut expected = Expected {
+        output: Vec::new(),
+        errors: Vec::new(),
+    };
+    for line in source.lines() {
+        let Some((_, comment)) = line.split_once("// ") else {
+            continue;
+        };
+        if let Some(text) = comment.strip_prefix("expect: ") {
+            expected.output.push(text.to_string());
+        }
+        for (annotation, kind) in KINDS {
+            if let Some(message) = comment
+                .strip_prefix(annotation)
+                .and_then(|rest| rest.strip_prefix(": "))
+            {
+                expected
+                    .errors
+                    .push((kind.to_string(), message.to_string()));
+            }
+        }
+    }
+    expected
+}
+
+/// The kind and message of every diagnostic in `stderr`. A diagnostic starts
+/// with `<Kind> Error`, optionally followed by ` at line L, column C`, and
+/// then `: <message>`; source snippets and `eprint` output are skipped.
+fn reported(stderr: &str) -> Vec<(String, String)> {
+    stderr
+        .lines()
+        .filter_map(|line| {
+            let (_, kind) = KINDS.iter().find(|(_, kind)| line.starts_with(kind))?;
+            let rest = &line[kind.len()..];
+            let message = match rest.strip_prefix(": ") {
+                Some(message) => message,
+                None if rest.starts_with(" at ") => rest.split_once(": ")?.1,
+                None => return None,
+            };
+            Some((kind.to_string(), message.to_string()))
+        })
+        .collect()
+}
+
+/// A fresh working directory for running `path` on `backend`.
+fn scratch(path: &Path, backend: &str) -> Result<PathBuf, String> {
+    let name = path
+        .with_extension("")
+        .to_string_lossy()
+        .replace(['/', '\\'], "-");
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR"))
+        .join("corpus")
+        .join(backend)
+        .join(name);
+    if dir.exists() {
+        fs::remove_dir_all(&dir).map_err(|e| e.to_string())?;
+    }
+    fs::create_dir_all(dir.join("dir")).map_err(|e| e.to_string())?;
+    Ok(dir)
+}
+
+fn check(path: &Path, backend: &str) -> Result<(), String> {
+    let source = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let expected = expected(&source);
+
+    let mut command = Command::new(env!("CARGO_BIN_EXE_interpreter"));
+    if backend == "vm" {
+        command.arg("--vm");
+    }
+    let output = command
+        .arg(fs::canonicalize(path).map_err(|e| e.to_string())?)
+        .current_dir(scratch(path, backend)?)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| e.to_string())?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    let printed: Vec<&str> = stdout.lines().collect();
+    if printed != expected.output {
+        return Err(format!(
+            "expected output {:?}\n  got {:?}",
+            expected.output, printed
+        ));
+    }
+    let errors = reported(&stderr);
+    if errors != expected.errors {
+        return Err(format!(
+            "expected errors {:?}\n  got {:?}\n  stderr: {}",
+            expected.errors, errors, stderr
+        ));
+    }
+    let exit_code = match expected.errors.first() {
+        Some((kind, _)) if kind == "Runtime Error" => 69,
+        Some(_) => 1,
+        None => 0,
+    };
+    if output.status.code() != Some(exit_code) {
+        return Err(format!(
+            "expected exit code {}, got {:?}\n  stderr: {}",
+            exit_code,
+            output.status.code(),
+            stderr
+        ));
+    }
+    Ok(())
+}
+
+fn run_corpus(backend: &str) {
+    let mut paths = Vec::new();
+    scripts(Path::new("tests"), &mut paths);
+    assert!(!paths.is_empty(), "no .nz scripts found under tests/");
+
+    let failures: Vec<String> = paths
+        .iter()
+        .filter_map(|path| {
+            let failure = check(path, backend).err()?;
+            Some(format!("{}: {}", path.display(), failure))
+        })
+        .collect();
+    assert!(
+        failures.is_empty(),
+        "{} of {} {} runs failed:\n{}",
+        failures.len(),
+        paths.len(),
+        backend,
+        failures.join("\n")
+    );
+}
+
+#[test]
+fn tree_walker() {
+    run_corpus("tree-walker");
+}
+
+#[test]
+fn vm() {
+    run_corpus("vm");
+}
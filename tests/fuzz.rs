@@ -0,0 +1,153 @@
+//! Feeds malformed source through every stage — scan, parse, resolve and run
+//! on both backends — and checks that each input ends in a result or an
+//! error, never a panic. The inputs are every prefix of the `.nz` scripts
+//! under `tests/` plus seeded random strings.
+
+use std::{
+    env, fs,
+    panic::{self, AssertUnwindSafe},
+    path::{Path, PathBuf},
+    thread,
+};
+
+use interpreter::{
+    interpreter::loxcallable::Arity,
+    parser::Parser,
+    resolver::Resolver,
+    scanner::Scanner,
+    vm::{Compiler, Vm},
+    Interpreter, NZErrors, Object,
+};
+
+// the same room `main` gives the tree-walker for `MAX_CALL_DEPTH` calls
+const STACK_SIZE: usize = 256 * 1024 * 1024;
+
+// running every prefix is quadratic in the script's length, so past this many
+// bytes (only the generated long literal gets there) scripts are cut at a
+// few evenly spaced boundaries instead
+const MAX_EXHAUSTIVE: usize = 8 * 1024;
+const SAMPLED_CUTS: usize = 16;
+
+const RANDOM_INPUTS: usize = 2000;
+const PIECES: [&str; 58] = [
+    "a", "b", "c", "x", "y", "z", "0", "1", "9", "_", " ", "\n", "\t", "\r", "\"", "\\", "$", "{",
+    "}", "(", ")", "[", "]", ";", ":", ".", ",", "+", "-", "*", "/", "%", "~", "=", "!", "<", ">",
+    "&", "|", "@", "#", "'", "é", "😀", "日", "/*", "*/", "///", "${", "\\u{", "fun ", "class ",
+    "var ", "print ", "return ", "this", "super", "=>",
+];
+
+fn scripts(dir: &Path, found: &mut Vec<PathBuf>) {
+    let mut entries: Vec<PathBuf> = fs::read_dir(dir)
+        .unwrap_or_else(|e| panic!("can't read {}: {}", dir.display(), e))
+        .map(|entry| entry.expect("directory entry").path())
+        .collect();
+    entries.sort();
+    for path in entries {
+        if path.is_dir() {
+            scripts(&path, found);
+        } else if path.extension().is_some_and(|extension| extension == "nz") {
+            found.push(path);
+        }
+    }
+}
+
+/// An interpreter whose `readLine` sees no input, so nothing waits on the
+/// test's stdin.
+fn interpreter() -> Interpreter {
+    let mut interpreter = Interpreter::new();
+    interpreter.define_native("readLine", Arity::Fixed(0), |_, _| Ok(Object::Nill));
+    interpreter
+}
+
+fn run(source: &str, vm: bool) -> Result<(), NZErrors> {
+    let tokens = Scanner::new(source.to_string()).scan_tokens()?;
+    let statements = Parser::new(tokens)
+        .parse()
+        .map_err(|mut errors| errors.remove(0))?;
+    let mut interpreter = interpreter();
+    Resolver::new(&mut interpreter).resolve(&statements)?;
+    if vm {
+        let script = Compiler::compile(&statements)?;
+        Vm::new(&mut interpreter).interpret(script)?;
+    } else {
+        interpreter.interpret(&statements)?;
+    }
+    Ok(())
+}
+
+/// Runs `check` on a thread with a deep enough stack, in a scratch working
+/// directory for the files scripts write. The working directory belongs to
+/// the whole process, so every test uses the same one.
+fn fuzz(check: impl FnOnce() + Send + 'static) {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("fuzz");
+    fs::create_dir_all(dir.join("dir")).expect("scratch directory");
+    env::set_current_dir(&dir).expect("scratch directory");
+    thread::Builder::new()
+        .stack_size(STACK_SIZE)
+        .spawn(check)
+        .expect("fuzz thread")
+        .join()
+        .expect("fuzz thread panicked");
+}
+
+/// The inputs on which some stage panicked, with the backend.
+fn panics<'a>(inputs: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+    let mut failures = Vec::new();
+    for input in inputs {
+        for vm in [false, true] {
+            if panic::catch_unwind(AssertUnwindSafe(|| run(input, vm))).is_err() {
+                let backend = if vm { "vm" } else { "tree-walker" };
+                failures.push(format!("[{}] {:?}", backend, input));
+            }
+        }
+    }
+    failures
+}
+
+#[test]
+fn script_prefixes() {
+    let mut paths = Vec::new();
+    scripts(
+        &Path::new(env!("CARGO_MANIFEST_DIR")).join("tests"),
+        &mut paths,
+    );
+    fuzz(move || {
+        let mut failures = Vec::new();
+        for path in &paths {
+            let source = fs::read_to_string(path).expect("script");
+            let boundaries = source.char_indices().map(|(i, _)| i).chain([source.len()]);
+            let cuts: Vec<usize> = if source.len() > MAX_EXHAUSTIVE {
+                let step = source.len().div_ceil(SAMPLED_CUTS);
+                boundaries
+                    .filter(|&cut| cut % step == 0 || cut == source.len())
+                    .collect()
+            } else {
+                boundaries.collect()
+            };
+            failures.extend(panics(cuts.iter().map(|&cut| &source[..cut])));
+        }
+        assert!(failures.is_empty(), "panicked on:\n{}", failures.join("\n"));
+    });
+}
+
+#[test]
+fn random_strings() {
+    fuzz(|| {
+        // xorshift64, so the inputs are the same on every run
+        let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state as usize
+        };
+        let inputs: Vec<String> = (0..RANDOM_INPUTS)
+            .map(|_| {
+                let len = next() % 41;
+                (0..len).map(|_| PIECES[next() % PIECES.len()]).collect()
+            })
+            .collect();
+        let failures = panics(inputs.iter().map(String::as_str));
+        assert!(failures.is_empty(), "panicked on:\n{}", failures.join("\n"));
+    });
+}